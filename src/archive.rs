@@ -0,0 +1,370 @@
+use anyhow::{bail, Context, Result};
+use colored::*;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tempfile::TempDir;
+
+// ARCHIVE TARGETS
+// `.zip`, `.tar`, `.tar.gz` and `.tgz` (local or URL) are unpacked into a temp dir and
+// scanned like any checkout. Entries that would land outside the dir (zip-slip) and links
+// are dropped. Each entry is written up to --max-size and then extended to its real
+// length as a sparse file, so the scanner's size checks and notes stay accurate without
+// the bytes ever touching the disk.
+
+// Ceiling on everything written, against archive bombs
+const UNPACK_LIMIT: u64 = 1024 * 1024 * 1024;
+
+const EXTENSIONS: [&str; 4] = [".zip", ".tar", ".tar.gz", ".tgz"];
+
+pub fn is_archive(target: &str) -> bool {
+    let name = target.split(['?', '#']).next().unwrap_or(target).to_lowercase();
+    EXTENSIONS.iter().any(|ext| name.ends_with(ext)) && (crate::remote::is_remote(target) || Path::new(target).is_file())
+}
+
+pub fn unpack(target: &str, max_size: u64) -> Result<TempDir> {
+    let bytes = if crate::remote::is_remote(target) {
+        crate::status!("{} Downloading {}", "[>>]".cyan().bold(), target);
+        match crate::remote::http_get(target, "*/*")? {
+            Ok(body) => body,
+            Err(e) => return Err(crate::remote::fetch_failure(&format!("Download failed: {}", target), &e)),
+        }
+    } else {
+        std::fs::read(target).with_context(|| format!("Failed to read {}", target))?
+    };
+
+    let dir = tempfile::Builder::new()
+        .prefix("gimtex_archive")
+        .tempdir()
+        .context("Failed to create temporary bunker")?;
+    let mut unpacker = Unpacker { root: dir.path().to_path_buf(), max_size, written: 0, files: 0, dropped: 0 };
+    if bytes.starts_with(b"PK") {
+        unpack_zip(&bytes, &mut unpacker)?;
+    } else if bytes.starts_with(b"\x1f\x8b") {
+        unpack_tar(flate2::read::MultiGzDecoder::new(bytes.as_slice()), &mut unpacker)?;
+    } else {
+        unpack_tar(bytes.as_slice(), &mut unpacker)?;
+    }
+
+    if unpacker.dropped > 0 {
+        crate::status!("{} Dropped {} archive entries (links or paths outside the archive)", "[!]".yellow().bold(), unpacker.dropped);
+    }
+    crate::status!("{} Unpacked {} file(s) from {}", "[OK]".green().bold(), unpacker.files, target);
+    Ok(dir)
+}
+
+struct Unpacker {
+    root: PathBuf,
+    max_size: u64,
+    written: u64,
+    files: usize,
+    dropped: usize,
+}
+
+impl Unpacker {
+    // Only plain relative components: no `..`, no absolute paths, no drive prefixes
+    fn destination(&mut self, name: &str) -> Option<PathBuf> {
+        let relative = Path::new(name);
+        let safe = relative.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !safe || relative.as_os_str().is_empty() {
+            self.dropped += 1;
+            return None;
+        }
+        Some(self.root.join(relative))
+    }
+
+    fn directory(&mut self, name: &str) -> Result<()> {
+        if let Some(path) = self.destination(name) {
+            std::fs::create_dir_all(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    fn file(&mut self, name: &str, size: u64, content: &mut dyn Read) -> Result<()> {
+        let Some(path) = self.destination(name) else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = File::create(&path).with_context(|| format!("Failed to write {}", path.display()))?;
+        // One byte past the remaining allowance is enough to know the limit is crossed
+        let allowance = self.max_size.min(UNPACK_LIMIT - self.written + 1);
+        let copied = std::io::copy(&mut content.take(allowance), &mut file)?;
+        self.written += copied;
+        if self.written > UNPACK_LIMIT {
+            bail!("Archive unpacks to more than {} MB; refusing to continue", UNPACK_LIMIT / (1024 * 1024));
+        }
+        if size > copied {
+            file.set_len(size)?;
+        }
+        file.flush()?;
+        self.files += 1;
+        Ok(())
+    }
+}
+
+fn unpack_zip(bytes: &[u8], unpacker: &mut Unpacker) -> Result<()> {
+    for entry in crate::zip::entries(bytes)? {
+        if entry.symlink {
+            unpacker.dropped += 1;
+            continue;
+        }
+        if entry.name.ends_with('/') {
+            unpacker.directory(&entry.name)?;
+            continue;
+        }
+        let content = crate::zip::read(bytes, &entry, unpacker.max_size)?;
+        unpacker.file(&entry.name, entry.size, &mut content.as_slice())?;
+    }
+    Ok(())
+}
+
+// ustar with the GNU (`L`) and PAX (`x`) long-name extensions
+fn unpack_tar(mut stream: impl Read, unpacker: &mut Unpacker) -> Result<()> {
+    let mut header = [0u8; 512];
+    let mut long_name: Option<String> = None;
+    loop {
+        if read_block(&mut stream, &mut header)? == 0 || header.iter().all(|&b| b == 0) {
+            return Ok(());
+        }
+        let size = tar_size(&header[124..136]).context("Corrupt tar header")?;
+        let padded = size.div_ceil(512) * 512;
+        let name = long_name.take().unwrap_or_else(|| {
+            let field = |range: std::ops::Range<usize>| {
+                let raw = &header[range];
+                String::from_utf8_lossy(&raw[..raw.iter().position(|&b| b == 0).unwrap_or(raw.len())]).to_string()
+            };
+            let (name, prefix) = (field(0..100), field(345..500));
+            if &header[257..262] == b"ustar" && !prefix.is_empty() { format!("{}/{}", prefix, name) } else { name }
+        });
+
+        let mut data = (&mut stream).take(padded);
+        match header[156] {
+            b'0' | 0 | b'7' => unpacker.file(&name, size, &mut (&mut data).take(size))?,
+            b'5' => unpacker.directory(&name)?,
+            b'L' => {
+                let mut raw = Vec::new();
+                (&mut data).take(size).read_to_end(&mut raw)?;
+                long_name = Some(String::from_utf8_lossy(&raw).trim_end_matches('\0').to_string());
+            }
+            b'x' => {
+                let mut raw = Vec::new();
+                (&mut data).take(size).read_to_end(&mut raw)?;
+                long_name = pax_path(&String::from_utf8_lossy(&raw));
+            }
+            // Global PAX headers carry no entry; links and devices are never unpacked
+            b'g' => {}
+            _ => unpacker.dropped += 1,
+        }
+        std::io::copy(&mut data, &mut std::io::sink())?;
+    }
+}
+
+// Fills the block, or returns 0 at a clean end of stream
+fn read_block(stream: &mut impl Read, block: &mut [u8; 512]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < block.len() {
+        match stream.read(&mut block[filled..])? {
+            0 if filled == 0 => return Ok(0),
+            0 => bail!("Truncated tar archive"),
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+// Octal, or base-256 (high bit set) for entries past 8 GB
+fn tar_size(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        return Some(field[1..].iter().fold(0u64, |n, &b| (n << 8) | b as u64));
+    }
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    if digits.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(digits, 8).ok()
+}
+
+// PAX records are `<len> key=value\n`
+fn pax_path(records: &str) -> Option<String> {
+    records.lines().find_map(|record| record.split_once(' ')?.1.strip_prefix("path=").map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tar_entry(name: &str, kind: u8, data: &[u8], link: &str) -> Vec<u8> {
+        let mut header = [0u8; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[157..157 + link.len()].copy_from_slice(link.as_bytes());
+        header[257..263].copy_from_slice(b"ustar\0");
+        let mut out = header.to_vec();
+        out.extend_from_slice(data);
+        out.resize(out.len().div_ceil(512) * 512, 0);
+        out
+    }
+
+    fn tar(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = entries.concat();
+        out.extend([0u8; 1024]);
+        out
+    }
+
+    // Stored entries: (name, data, symlink, declared size)
+    fn zip(entries: &[(&str, &[u8], bool, u32)]) -> Vec<u8> {
+        let (mut out, mut central) = (Vec::new(), Vec::new());
+        for &(name, data, symlink, size) in entries {
+            let offset = out.len() as u32;
+            out.extend(b"PK\x03\x04");
+            out.extend([20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            out.extend((data.len() as u32).to_le_bytes());
+            out.extend(size.to_le_bytes());
+            out.extend((name.len() as u16).to_le_bytes());
+            out.extend([0, 0]);
+            out.extend(name.as_bytes());
+            out.extend(data);
+
+            let mode: u32 = if symlink { 0o120777 } else { 0o100644 };
+            central.extend(b"PK\x01\x02");
+            central.extend([20, 3, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            central.extend((data.len() as u32).to_le_bytes());
+            central.extend(size.to_le_bytes());
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend([0, 0, 0, 0, 0, 0, 0, 0]);
+            central.extend((mode << 16).to_le_bytes());
+            central.extend(offset.to_le_bytes());
+            central.extend(name.as_bytes());
+        }
+        let (cd_offset, cd_len) = (out.len() as u32, central.len() as u32);
+        out.extend(central);
+        out.extend(b"PK\x05\x06");
+        out.extend([0, 0, 0, 0]);
+        out.extend((entries.len() as u16).to_le_bytes());
+        out.extend((entries.len() as u16).to_le_bytes());
+        out.extend(cd_len.to_le_bytes());
+        out.extend(cd_offset.to_le_bytes());
+        out.extend([0, 0]);
+        out
+    }
+
+    fn fresh(root: &Path) -> Unpacker {
+        Unpacker { root: root.to_path_buf(), max_size: 1024, written: 0, files: 0, dropped: 0 }
+    }
+
+    fn contents(root: &Path) -> Vec<String> {
+        let mut found: Vec<String> = ignore::WalkBuilder::new(root)
+            .standard_filters(false)
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path() != root)
+            .map(|e| e.path().strip_prefix(root).unwrap().display().to_string())
+            .collect();
+        found.sort();
+        found
+    }
+
+    const ESCAPES: [&str; 4] = ["../evil.txt", "/tmp/gimtex_absolute.txt", "ok/../../evil.txt", "ok/../../../evil.txt"];
+
+    #[test]
+    fn tar_entries_outside_the_root_are_dropped() {
+        let outer = tempfile::tempdir().unwrap();
+        let root = outer.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        let mut entries: Vec<Vec<u8>> = ESCAPES.iter().map(|name| tar_entry(name, b'0', b"pwned", "")).collect();
+        entries.push(tar_entry("ok/file.txt", b'0', b"fine", ""));
+        let mut unpacker = fresh(&root);
+        unpack_tar(tar(&entries).as_slice(), &mut unpacker).unwrap();
+        assert_eq!((unpacker.files, unpacker.dropped), (1, ESCAPES.len()));
+        assert_eq!(contents(outer.path()), ["root", "root/ok", "root/ok/file.txt"]);
+        assert!(!Path::new("/tmp/gimtex_absolute.txt").exists());
+    }
+
+    #[test]
+    fn zip_entries_outside_the_root_are_dropped() {
+        let outer = tempfile::tempdir().unwrap();
+        let root = outer.path().join("root");
+        std::fs::create_dir(&root).unwrap();
+        let mut entries: Vec<(&str, &[u8], bool, u32)> = ESCAPES.iter().map(|&name| (name, &b"pwned"[..], false, 5)).collect();
+        entries.push(("ok/file.txt", b"fine", false, 4));
+        let mut unpacker = fresh(&root);
+        unpack_zip(&zip(&entries), &mut unpacker).unwrap();
+        assert_eq!((unpacker.files, unpacker.dropped), (1, ESCAPES.len()));
+        assert_eq!(contents(outer.path()), ["root", "root/ok", "root/ok/file.txt"]);
+    }
+
+    #[test]
+    fn links_are_never_unpacked() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = tar(&[
+            tar_entry("passwd", b'2', b"", "/etc/passwd"),
+            tar_entry("hard", b'1', b"", "/etc/passwd"),
+            // A file written through the symlink name would escape if the link existed
+            tar_entry("passwd", b'0', b"overwrite", ""),
+        ]);
+        let mut unpacker = fresh(dir.path());
+        unpack_tar(archive.as_slice(), &mut unpacker).unwrap();
+        assert_eq!((unpacker.files, unpacker.dropped), (1, 2));
+        assert!(!dir.path().join("passwd").is_symlink());
+        assert!(!dir.path().join("hard").exists());
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut unpacker = fresh(dir.path());
+        unpack_zip(&zip(&[("link", b"/etc/passwd", true, 11)]), &mut unpacker).unwrap();
+        assert_eq!((unpacker.files, unpacker.dropped), (0, 1));
+        assert!(contents(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn entries_past_max_size_keep_their_length_sparse() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut unpacker = fresh(dir.path());
+        unpack_tar(tar(&[tar_entry("big.txt", b'0', &[b'a'; 4096], "")]).as_slice(), &mut unpacker).unwrap();
+        assert_eq!(std::fs::metadata(dir.path().join("big.txt")).unwrap().len(), 4096);
+        assert_eq!(unpacker.written, 1024);
+    }
+
+    #[test]
+    fn unpack_limit_stops_the_unpack() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut unpacker = fresh(dir.path());
+        unpacker.written = UNPACK_LIMIT - 10;
+        let err = unpack_tar(tar(&[tar_entry("bomb.txt", b'0', &[b'a'; 1000], "")]).as_slice(), &mut unpacker).unwrap_err();
+        assert!(err.to_string().contains("refusing to continue"), "{}", err);
+        // Writing stops one byte past the allowance, not at the end of the entry
+        assert_eq!(std::fs::metadata(dir.path().join("bomb.txt")).unwrap().len(), 11);
+    }
+
+    #[test]
+    fn zip64_fails_before_anything_is_written() {
+        let saturated = zip(&[("first.txt", b"fine", false, 4), ("huge.bin", b"", false, u32::MAX)]);
+        let mut locator = zip(&[("first.txt", b"fine", false, 4)]);
+        let end = locator.len() - 22;
+        locator.splice(end..end, *b"PK\x06\x07\0\0\0\0\0\0\0\0\0\0\0\0\x01\0\0\0");
+        for archive in [saturated, locator] {
+            let dir = tempfile::tempdir().unwrap();
+            let err = unpack_zip(&archive, &mut fresh(dir.path())).unwrap_err();
+            assert!(err.to_string().contains("ZIP64"), "{}", err);
+            assert!(contents(dir.path()).is_empty());
+        }
+    }
+
+    #[test]
+    fn unpack_reads_zip_and_gzipped_tar_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("src.zip");
+        std::fs::write(&zip_path, zip(&[("src/lib.rs", b"pub fn f() {}\n", false, 14)])).unwrap();
+        let unpacked = unpack(zip_path.to_str().unwrap(), 1024).unwrap();
+        assert_eq!(std::fs::read_to_string(unpacked.path().join("src/lib.rs")).unwrap(), "pub fn f() {}\n");
+
+        let tgz_path = dir.path().join("src.tgz");
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&tar(&[tar_entry("main.py", b'0', b"print(1)\n", "")])).unwrap();
+        std::fs::write(&tgz_path, gz.finish().unwrap()).unwrap();
+        let unpacked = unpack(tgz_path.to_str().unwrap(), 1024).unwrap();
+        assert_eq!(std::fs::read_to_string(unpacked.path().join("main.py")).unwrap(), "print(1)\n");
+    }
+}
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+// COMMENT STRIPPING
+// `--strip-comments`: a small per-language tokenizer that knows where comments start and,
+// just as important, which string literals to step over (`"http://..."` is not a comment).
+// Removed comments keep their newlines so every output line maps onto the same input
+// line; lines left empty by the removal are dropped afterwards unless line numbers or
+// blame need the original line count.
+
+struct Syntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    // String delimiters, longest first
+    strings: &'static [&'static str],
+    // Delimiters whose strings may span lines; the rest end at the newline
+    multiline: &'static [&'static str],
+    // Comment prefixes that mark documentation
+    doc: &'static [&'static str],
+    // Line comments only count at line start or after whitespace (`#` in `$#`, `url#frag`)
+    spaced: bool,
+    // Rust: `'` opens a char literal, but not a lifetime
+    char_literals: bool,
+}
+
+const C_DOC: &[&str] = &["///", "//!", "/**", "/*!"];
+const C_BLOCK: &[(&str, &str)] = &[("/*", "*/")];
+
+fn syntax(path: &Path) -> Option<Syntax> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let c_like = |strings, multiline| Syntax { line: &["//"], block: C_BLOCK, strings, multiline, doc: C_DOC, spaced: false, char_literals: false };
+    let hash = |strings, multiline| Syntax { line: &["#"], block: &[], strings, multiline, doc: &[], spaced: true, char_literals: false };
+    Some(match extension.as_str() {
+        "rs" => Syntax { char_literals: true, ..c_like(&["\"", "'"], &["\""]) },
+        "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "cs" | "dart" | "scss" | "less" => c_like(&["\"", "'"], &[]),
+        "kt" | "swift" | "scala" => c_like(&["\"\"\"", "\"", "'"], &["\"\"\""]),
+        "go" => c_like(&["\"", "'", "`"], &["`"]),
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => c_like(&["\"", "'", "`"], &["`"]),
+        "php" => Syntax { line: &["//", "#"], ..c_like(&["\"", "'"], &["\"", "'"]) },
+        "css" => Syntax { line: &[], ..c_like(&["\"", "'"], &[]) },
+        "py" | "toml" => hash(&["\"\"\"", "'''", "\"", "'"], &["\"\"\"", "'''"]),
+        "sh" | "bash" | "zsh" | "rb" | "pl" | "r" | "yml" | "yaml" | "conf" | "cmake" => hash(&["\"", "'"], &[]),
+        "sql" => Syntax { line: &["--"], block: C_BLOCK, strings: &["'", "\""], multiline: &["'"], doc: &[], spaced: false, char_literals: false },
+        "lua" => Syntax { line: &["--"], block: &[("--[[", "]]")], strings: &["\"", "'"], multiline: &[], doc: &["---"], spaced: false, char_literals: false },
+        "hs" => Syntax { line: &["--"], block: &[("{-", "-}")], strings: &["\""], multiline: &[], doc: &["-- |", "{-|"], spaced: false, char_literals: false },
+        "html" | "htm" | "xml" | "vue" | "svelte" => Syntax { line: &[], block: &[("<!--", "-->")], strings: &[], multiline: &[], doc: &[], spaced: false, char_literals: false },
+        _ if name == "makefile" || name == "dockerfile" || name.starts_with("dockerfile.") => hash(&["\"", "'"], &[]),
+        _ => return None,
+    })
+}
+
+// None when the language isn't known; the text then goes through untouched
+pub fn strip(path: &Path, content: &str, keep_docs: bool, keep_lines: bool) -> Option<String> {
+    let syntax = syntax(path)?;
+    let stripped = remove_comments(content, &syntax, keep_docs);
+
+    let mut out = String::new();
+    for (index, (original, line)) in content.lines().zip(stripped.lines()).enumerate() {
+        let emptied = line.trim().is_empty() && !original.trim().is_empty();
+        // A shebang is an instruction, not a comment
+        if index == 0 && original.starts_with("#!") && syntax.spaced {
+            out.push_str(original);
+        } else if emptied && !keep_lines {
+            continue;
+        } else if line.len() < original.len() {
+            out.push_str(line.trim_end());
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    Some(out)
+}
+
+fn remove_comments(content: &str, syntax: &Syntax, keep_docs: bool) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut i = 0;
+    while i < content.len() {
+        let rest = &content[i..];
+        let at_word_start = i == 0 || content[..i].ends_with(char::is_whitespace);
+
+        if let Some(&(open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+            let end = rest[open.len()..].find(close).map_or(rest.len(), |p| open.len() + p + close.len());
+            let comment = &rest[..end];
+            if keep_docs && is_doc(comment, syntax) {
+                out.push_str(comment);
+            } else {
+                out.extend(comment.chars().filter(|&c| c == '\n'));
+            }
+            i += end;
+            continue;
+        }
+        if syntax.line.iter().any(|marker| rest.starts_with(marker)) && (!syntax.spaced || at_word_start) {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            if keep_docs && is_doc(&rest[..end], syntax) {
+                out.push_str(&rest[..end]);
+            }
+            i += end;
+            continue;
+        }
+        if let Some(delimiter) = string_start(rest, syntax) {
+            let end = string_end(rest, delimiter, syntax.multiline.contains(&delimiter));
+            out.push_str(&rest[..end]);
+            i += end;
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap_or(' ');
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+fn is_doc(comment: &str, syntax: &Syntax) -> bool {
+    syntax.doc.iter().any(|prefix| comment.starts_with(prefix)) && !comment.starts_with("/**/") && !comment.starts_with("////")
+}
+
+fn string_start(rest: &str, syntax: &Syntax) -> Option<&'static str> {
+    let delimiter = *syntax.strings.iter().find(|d| rest.starts_with(*d))?;
+    if syntax.char_literals && delimiter == "'" {
+        // `'x'` or `'\n'`; anything else (`'a`, `'static`) is a lifetime
+        let mut chars = rest.chars().skip(1);
+        return match (chars.next(), chars.next()) {
+            (Some('\\'), _) => Some(delimiter),
+            (Some(_), Some('\'')) => Some(delimiter),
+            _ => None,
+        };
+    }
+    Some(delimiter)
+}
+
+// Byte length of the literal, delimiters included; unterminated ones run to the line end
+fn string_end(rest: &str, delimiter: &str, multiline: bool) -> usize {
+    let mut chars = rest.char_indices().skip(delimiter.chars().count()).peekable();
+    while let Some((at, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c == '\n' && !multiline {
+            return at;
+        } else if rest[at..].starts_with(delimiter) {
+            return at + delimiter.len();
+        }
+    }
+    rest.len()
+}
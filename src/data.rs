@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// DATA PREVIEWS
+// `--data-preview N`: CSV/TSV, JSON and JSON Lines files holding more than N rows are
+// replaced by an inferred schema and their first N rows. Smaller files go through
+// verbatim. Parquet is binary and stays listed as such.
+
+// Rows inspected for type inference
+const SAMPLE_ROWS: usize = 1000;
+
+pub fn kind(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "csv" => Some("CSV"),
+        "tsv" => Some("TSV"),
+        "json" => Some("JSON"),
+        "jsonl" | "ndjson" => Some("JSON Lines"),
+        _ => None,
+    }
+}
+
+// None when the file is small enough to include whole, or doesn't parse
+pub fn preview(kind: &str, content: &str, rows: usize) -> Option<String> {
+    match kind {
+        "CSV" => delimited(content, ',', kind, rows),
+        "TSV" => delimited(content, '\t', kind, rows),
+        "JSON Lines" => json_lines(content, rows),
+        _ => json(content, rows),
+    }
+}
+
+fn delimited(content: &str, delimiter: char, kind: &str, rows: usize) -> Option<String> {
+    let records = split_records(content, delimiter);
+    let (header, body) = records.split_first()?;
+    if body.len() <= rows {
+        return None;
+    }
+
+    let mut types = vec![ColumnType::default(); header.1.len()];
+    for (_, fields) in body.iter().take(SAMPLE_ROWS) {
+        for (column, field) in types.iter_mut().zip(fields) {
+            column.observe(field);
+        }
+    }
+
+    let mut out = format!("Data preview: {} rows x {} columns ({}; first {} shown)\n\nColumns:\n", body.len(), header.1.len(), kind, rows);
+    for (name, column) in header.1.iter().zip(&types) {
+        out.push_str(&format!("  {}: {}\n", name, column.describe()));
+    }
+    out.push('\n');
+    for (raw, _) in records.iter().take(rows + 1) {
+        out.push_str(raw);
+        out.push('\n');
+    }
+    out.push_str(&format!("... {} more rows\n", body.len() - rows));
+    Some(out)
+}
+
+// (raw text, fields) per record; quoted fields may hold delimiters, quotes ("") and newlines
+fn split_records(content: &str, delimiter: char) -> Vec<(&str, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut start = 0;
+    let mut chars = content.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek().map(|&(_, n)| n) == Some('"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                let raw = content[start..at].trim_end_matches('\r');
+                if !raw.is_empty() {
+                    records.push((raw, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                start = at + 1;
+            }
+            '\r' if !quoted => {}
+            c => field.push(c),
+        }
+    }
+    if start < content.len() {
+        fields.push(field);
+        records.push((content[start..].trim_end_matches('\r'), fields));
+    }
+    records
+}
+
+#[derive(Clone, Default)]
+struct ColumnType {
+    kind: Option<&'static str>,
+    nullable: bool,
+}
+
+impl ColumnType {
+    fn observe(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("null") || value.eq_ignore_ascii_case("na") {
+            self.nullable = true;
+            return;
+        }
+        let kind = if value.parse::<i64>().is_ok() {
+            "integer"
+        } else if value.parse::<f64>().is_ok() {
+            "float"
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            "boolean"
+        } else if is_date(value) {
+            if value.len() > 10 { "datetime" } else { "date" }
+        } else {
+            "string"
+        };
+        self.kind = Some(match (self.kind, kind) {
+            (None, k) => k,
+            (Some(a), b) if a == b => a,
+            (Some("integer"), "float") | (Some("float"), "integer") => "float",
+            (Some("date"), "datetime") | (Some("datetime"), "date") => "datetime",
+            _ => "string",
+        });
+    }
+
+    fn describe(&self) -> String {
+        let kind = self.kind.unwrap_or("empty");
+        if self.nullable && self.kind.is_some() { format!("{} (nullable)", kind) } else { kind.to_string() }
+    }
+}
+
+// `YYYY-MM-DD`, optionally followed by a time
+fn is_date(value: &str) -> bool {
+    let b = value.as_bytes();
+    b.len() >= 10
+        && b[..4].iter().all(u8::is_ascii_digit)
+        && b[4] == b'-'
+        && b[5..7].iter().all(u8::is_ascii_digit)
+        && b[7] == b'-'
+        && b[8..10].iter().all(u8::is_ascii_digit)
+        && (b.len() == 10 || matches!(b[10], b'T' | b' '))
+}
+
+fn json_lines(content: &str, rows: usize) -> Option<String> {
+    let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.len() <= rows {
+        return None;
+    }
+    let sample: Vec<serde_json::Value> = lines.iter().take(SAMPLE_ROWS).filter_map(|l| serde_json::from_str(l).ok()).collect();
+    if sample.is_empty() {
+        return None;
+    }
+    let mut out = format!("Data preview: {} records (JSON Lines; first {} shown)\n\nFields:\n", lines.len(), rows);
+    out.push_str(&object_schema(&sample, "  "));
+    out.push('\n');
+    for line in lines.iter().take(rows) {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str(&format!("... {} more records\n", lines.len() - rows));
+    Some(out)
+}
+
+// Top-level arrays are previewed like rows; objects list their keys, and arrays under
+// them longer than N are cut to N items
+fn json(content: &str, rows: usize) -> Option<String> {
+    let doc: serde_json::Value = serde_json::from_str(content).ok()?;
+    match &doc {
+        serde_json::Value::Array(items) if items.len() > rows => {
+            let mut out = format!("Data preview: array of {} items (JSON; first {} shown)\n\n", items.len(), rows);
+            if items.iter().take(SAMPLE_ROWS).all(|v| v.is_object()) {
+                out.push_str("Item fields:\n");
+                out.push_str(&object_schema(&items[..items.len().min(SAMPLE_ROWS)], "  "));
+                out.push('\n');
+            }
+            for item in items.iter().take(rows) {
+                out.push_str(&serde_json::to_string(item).ok()?);
+                out.push('\n');
+            }
+            out.push_str(&format!("... {} more items\n", items.len() - rows));
+            Some(out)
+        }
+        serde_json::Value::Object(map) if map.values().any(|v| v.as_array().is_some_and(|a| a.len() > rows)) => {
+            let mut out = format!("Data preview: object with {} keys (JSON; arrays cut to {} items)\n\nKeys:\n", map.len(), rows);
+            for (key, value) in map {
+                out.push_str(&format!("  {}: {}\n", key, describe(value)));
+            }
+            let mut trimmed = map.clone();
+            for value in trimmed.values_mut() {
+                if let serde_json::Value::Array(items) = value {
+                    items.truncate(rows);
+                }
+            }
+            out.push('\n');
+            out.push_str(&serde_json::to_string_pretty(&trimmed).ok()?);
+            out.push('\n');
+            Some(out)
+        }
+        _ => None,
+    }
+}
+
+// `key: type` per field seen across the sample, with how often it was present
+fn object_schema(sample: &[serde_json::Value], indent: &str) -> String {
+    let mut fields: BTreeMap<&str, (usize, Vec<&'static str>)> = BTreeMap::new();
+    for object in sample.iter().filter_map(|v| v.as_object()) {
+        for (key, value) in object {
+            let entry = fields.entry(key.as_str()).or_default();
+            entry.0 += 1;
+            let kind = json_type(value);
+            if !entry.1.contains(&kind) {
+                entry.1.push(kind);
+            }
+        }
+    }
+    let mut out = String::new();
+    for (key, (seen, kinds)) in fields {
+        let optional = if seen < sample.len() { " (optional)" } else { "" };
+        out.push_str(&format!("{}{}: {}{}\n", indent, key, kinds.join(" | "), optional));
+    }
+    out
+}
+
+fn json_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(n) if n.is_f64() => "float",
+        serde_json::Value::Number(_) => "integer",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+fn describe(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Array(items) => format!("array of {} items", items.len()),
+        serde_json::Value::Object(map) => format!("object with {} keys", map.len()),
+        other => json_type(other).to_string(),
+    }
+}
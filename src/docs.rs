@@ -0,0 +1,364 @@
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::path::Path;
+
+// DOCUMENT TEXT
+// `--docs`: specs and design notes checked in as PDF or DOCX become plain text. DOCX is a
+// zip around WordprocessingML; PDF text comes from the Tj/TJ operators of the (usually
+// deflated) content streams. Both are best effort: layout is reduced to lines and
+// paragraphs, and PDFs with CID fonts and no plain encoding come out empty.
+
+// Ceiling on the decompressed XML or content stream, against zip bombs
+const INFLATE_LIMIT: u64 = 64 * 1024 * 1024;
+
+pub fn kind(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+        "pdf" => Some("PDF"),
+        "docx" => Some("DOCX"),
+        _ => None,
+    }
+}
+
+pub fn extract(kind: &str, bytes: &[u8]) -> Result<String> {
+    let text = match kind {
+        "DOCX" => docx_text(bytes)?,
+        _ => pdf_text(bytes),
+    };
+    Ok(tidy(&text))
+}
+
+// Trailing spaces off, runs of blank lines down to one
+fn tidy(text: &str) -> String {
+    let mut out = String::new();
+    let mut blanks = 0;
+    for line in text.lines().map(str::trim_end) {
+        if line.trim().is_empty() {
+            blanks += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blanks > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(line);
+        blanks = 0;
+    }
+    out
+}
+
+fn docx_text(bytes: &[u8]) -> Result<String> {
+    let entries = crate::zip::entries(bytes)?;
+    let body = entries.iter().find(|e| e.name == "word/document.xml").context("No word/document.xml in DOCX")?;
+    let xml = crate::zip::read(bytes, body, INFLATE_LIMIT)?;
+    let xml = String::from_utf8_lossy(&xml);
+
+    // Only <w:t> runs carry text; paragraphs, breaks and tabs carry the layout
+    let mut out = String::new();
+    let mut in_text = false;
+    let mut rest: &str = &xml;
+    while let Some(open) = rest.find('<') {
+        if in_text {
+            out.push_str(&unescape(&rest[..open]));
+        }
+        let Some(close) = rest[open..].find('>') else { break };
+        let tag = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+        let closing = tag.starts_with('/');
+        let name = tag.trim_start_matches('/').split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("");
+        match (name, closing) {
+            ("w:t", false) => in_text = !tag.ends_with('/'),
+            ("w:t", true) => in_text = false,
+            ("w:p", true) | ("w:br", false) | ("w:cr", false) => out.push('\n'),
+            ("w:tab", false) => out.push('\t'),
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse::<u32>))
+                .and_then(|n| n.ok())
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn pdf_text(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut at = 0;
+    while let Some(found) = find(bytes, b"stream", at) {
+        at = found + 6;
+        // `endstream` contains `stream` too
+        if bytes[..found].ends_with(b"end") {
+            continue;
+        }
+        let data_start = match bytes.get(at..) {
+            Some([b'\r', b'\n', ..]) => at + 2,
+            Some([b'\n', ..]) | Some([b'\r', ..]) => at + 1,
+            _ => continue,
+        };
+        let Some(data_end) = find(bytes, b"endstream", data_start) else { break };
+        at = data_end + 9;
+
+        // The stream dictionary sits between the object header and the `stream` keyword
+        let dict_start = rfind(&bytes[..found], b"obj").unwrap_or(0);
+        let dict = String::from_utf8_lossy(&bytes[dict_start..found]);
+        if ["/XRef", "/ObjStm", "/Image", "/Metadata", "/FontFile", "/Length1"].iter().any(|k| dict.contains(k)) {
+            continue;
+        }
+        let data = &bytes[data_start..data_end];
+        let content = if dict.contains("/FlateDecode") {
+            let mut inflated = Vec::new();
+            if flate2::read::ZlibDecoder::new(data).take(INFLATE_LIMIT).read_to_end(&mut inflated).is_err() && inflated.is_empty() {
+                continue;
+            }
+            inflated
+        } else if dict.contains("/Filter") {
+            continue;
+        } else {
+            data.to_vec()
+        };
+        out.push_str(&content_text(&content));
+    }
+    out
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack.get(from..)?.windows(needle.len()).position(|w| w == needle).map(|p| p + from)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|w| w == needle).map(|p| p + needle.len())
+}
+
+enum Operand {
+    Number(f64),
+    Text(String),
+    Array(Vec<Operand>),
+}
+
+// Walks a content stream, keeping operands until their operator shows up
+fn content_text(content: &[u8]) -> String {
+    let mut out = String::new();
+    let mut stack: Vec<Operand> = Vec::new();
+    let mut arrays: Vec<Vec<Operand>> = Vec::new();
+    let mut last_y: Option<f64> = None;
+    let mut i = 0;
+    while i < content.len() {
+        let c = content[i];
+        let operand = match c {
+            b'(' => {
+                let (text, end) = literal_string(content, i + 1);
+                i = end;
+                Some(Operand::Text(text))
+            }
+            b'<' if content.get(i + 1) != Some(&b'<') => {
+                let end = content[i..].iter().position(|&b| b == b'>').map_or(content.len(), |p| i + p);
+                let text = hex_string(&content[i + 1..end]);
+                i = end + 1;
+                Some(Operand::Text(text))
+            }
+            b'[' => {
+                arrays.push(Vec::new());
+                i += 1;
+                None
+            }
+            b']' => {
+                i += 1;
+                arrays.pop().map(Operand::Array)
+            }
+            b'%' => {
+                while i < content.len() && content[i] != b'\n' && content[i] != b'\r' {
+                    i += 1;
+                }
+                None
+            }
+            b'<' | b'>' => {
+                // Dictionary brackets (marked content properties); their names are skipped below
+                i += if content.get(i + 1) == Some(&c) { 2 } else { 1 };
+                None
+            }
+            b'/' | b'{' | b'}' => {
+                // Names are skipped
+                i += 1;
+                while i < content.len() && !is_delimiter(content[i]) {
+                    i += 1;
+                }
+                None
+            }
+            _ if c.is_ascii_whitespace() => {
+                i += 1;
+                None
+            }
+            _ => {
+                let start = i;
+                while i < content.len() && !is_delimiter(content[i]) {
+                    i += 1;
+                }
+                let word = &content[start..i.max(start + 1)];
+                i = i.max(start + 1);
+                match std::str::from_utf8(word).ok().and_then(|w| w.parse::<f64>().ok()) {
+                    Some(n) => Some(Operand::Number(n)),
+                    None => {
+                        if arrays.is_empty() {
+                            apply(word, &stack, &mut last_y, &mut out);
+                            stack.clear();
+                        }
+                        None
+                    }
+                }
+            }
+        };
+        if let Some(operand) = operand {
+            match arrays.last_mut() {
+                Some(array) => array.push(operand),
+                None => stack.push(operand),
+            }
+        }
+    }
+    out
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace() || matches!(b, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+fn apply(operator: &[u8], stack: &[Operand], last_y: &mut Option<f64>, out: &mut String) {
+    let number = |from_end: usize| match stack.len().checked_sub(from_end).and_then(|i| stack.get(i)) {
+        Some(Operand::Number(n)) => Some(*n),
+        _ => None,
+    };
+    match operator {
+        b"Tj" | b"'" | b"\"" => {
+            if operator != b"Tj" {
+                out.push('\n');
+            }
+            if let Some(Operand::Text(text)) = stack.last() {
+                out.push_str(text);
+            }
+        }
+        b"TJ" => {
+            if let Some(Operand::Array(items)) = stack.last() {
+                for item in items {
+                    match item {
+                        Operand::Text(text) => out.push_str(text),
+                        // Large negative kerning is how PDFs write word gaps
+                        Operand::Number(n) if *n < -180.0 => out.push(' '),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        b"T*" | b"ET" => out.push('\n'),
+        b"Td" | b"TD" if number(1).is_some_and(|ty| ty != 0.0) => out.push('\n'),
+        b"Tm" => {
+            let y = number(1);
+            if last_y.is_some() && y != *last_y {
+                out.push('\n');
+            }
+            *last_y = y;
+        }
+        _ => {}
+    }
+}
+
+// A `( ... )` string with balanced parentheses and backslash escapes; returns the text
+// and the index after the closing parenthesis
+fn literal_string(content: &[u8], mut i: usize) -> (String, usize) {
+    let mut bytes = Vec::new();
+    let mut depth = 0;
+    while i < content.len() {
+        let c = content[i];
+        i += 1;
+        match c {
+            b'\\' => {
+                let Some(&next) = content.get(i) else { break };
+                i += 1;
+                match next {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' | b'f' => {}
+                    b'0'..=b'7' => {
+                        let mut value = (next - b'0') as u32;
+                        for _ in 0..2 {
+                            match content.get(i) {
+                                Some(&d @ b'0'..=b'7') => {
+                                    value = value * 8 + (d - b'0') as u32;
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        bytes.push(value as u8);
+                    }
+                    b'\r' | b'\n' => {
+                        if next == b'\r' && content.get(i) == Some(&b'\n') {
+                            i += 1;
+                        }
+                    }
+                    other => bytes.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                bytes.push(c);
+            }
+            b')' if depth == 0 => break,
+            b')' => {
+                depth -= 1;
+                bytes.push(c);
+            }
+            _ => bytes.push(c),
+        }
+    }
+    (decode_pdf_bytes(&bytes), i)
+}
+
+fn hex_string(hex: &[u8]) -> String {
+    let digits: Vec<u8> = hex.iter().filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8)).collect();
+    let bytes: Vec<u8> = digits.chunks(2).map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)).collect();
+    decode_pdf_bytes(&bytes)
+}
+
+// UTF-16BE when marked (or when every other byte is zero), PDFDocEncoding/Latin-1 otherwise
+fn decode_pdf_bytes(bytes: &[u8]) -> String {
+    let utf16 = bytes.starts_with(b"\xFE\xFF") || (bytes.len() >= 2 && bytes.len().is_multiple_of(2) && bytes.iter().step_by(2).all(|&b| b == 0));
+    if utf16 {
+        let body = bytes.strip_prefix(b"\xFE\xFF").unwrap_or(bytes);
+        let units: Vec<u16> = body.chunks_exact(2).map(|p| u16::from_be_bytes([p[0], p[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    bytes.iter().map(|&b| b as char).filter(|c| !c.is_control() || *c == '\n' || *c == '\t').collect()
+}
@@ -0,0 +1,185 @@
+// TEXT DECODING
+// No encoding crates in the tree, and the cases that matter are few: UTF-8 (with or
+// without BOM), UTF-16 from Windows tooling (BOM or not), and legacy single-byte files,
+// which are read as Windows-1252 (a superset of Latin-1 for printable text). Every guess
+// that isn't backed by a BOM or valid UTF-8 has to decode to mostly printable text first,
+// so binary data with the wrong shape of zeros or high bytes still classifies as binary.
+
+// Bytes this decoding looks at to classify a file
+const SAMPLE: usize = 1024;
+
+// Share of a guessed decoding that must be printable, in percent (tabs and newlines count)
+const PRINTABLE: usize = 95;
+
+// The text plus the source encoding when it wasn't UTF-8; None for binary data. A UTF-8
+// BOM survives as U+FEFF (normalization strips it), UTF-16 BOMs are consumed.
+pub fn decode(bytes: &[u8]) -> Option<(String, Option<&'static str>)> {
+    if bytes.starts_with(b"\xEF\xBB\xBF") {
+        return Some((String::from_utf8_lossy(bytes).to_string(), None));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return Some((utf16(rest, u16::from_le_bytes), Some("UTF-16LE")));
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return Some((utf16(rest, u16::from_be_bytes), Some("UTF-16BE")));
+    }
+    match bom_less_utf16(bytes) {
+        Some(true) => return Some((utf16(bytes, u16::from_le_bytes), Some("UTF-16LE"))),
+        Some(false) => return Some((utf16(bytes, u16::from_be_bytes), Some("UTF-16BE"))),
+        None => {}
+    }
+
+    if bytes.iter().take(SAMPLE).any(|&b| b == 0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some((text.to_string(), None)),
+        // A multi-byte char cut off at the end (truncated read) is still UTF-8
+        Err(e) if e.error_len().is_none() => Some((String::from_utf8_lossy(bytes).to_string(), None)),
+        Err(_) => {
+            let sample: String = bytes.iter().take(SAMPLE).map(|&b| windows_1252(b)).collect();
+            if !mostly_printable(sample.chars()) {
+                return None;
+            }
+            Some((bytes.iter().map(|&b| windows_1252(b)).collect(), Some("Windows-1252")))
+        }
+    }
+}
+
+fn printable(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\x0C') || !(c.is_control() || c == char::REPLACEMENT_CHARACTER)
+}
+
+fn mostly_printable(chars: impl Iterator<Item = char>) -> bool {
+    let (mut total, mut good) = (0, 0);
+    for c in chars {
+        total += 1;
+        good += printable(c) as usize;
+    }
+    good * 100 >= total * PRINTABLE
+}
+
+fn utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+// ASCII-heavy UTF-16 has a zero in every other byte: odd positions for LE, even for BE.
+// Some(true) = LE, Some(false) = BE. Arrays of small integers have the same zeros, so the
+// sample must also decode without lone surrogates to mostly printable characters.
+fn bom_less_utf16(bytes: &[u8]) -> Option<bool> {
+    let sample = &bytes[..bytes.len().min(SAMPLE) & !1];
+    if sample.len() < 4 {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let zeros_at = |parity: usize| sample.iter().skip(parity).step_by(2).filter(|&&b| b == 0).count();
+    let (even, odd) = (zeros_at(0), zeros_at(1));
+    let little = if odd * 10 >= pairs * 4 && even * 20 < pairs {
+        true
+    } else if even * 10 >= pairs * 4 && odd * 20 < pairs {
+        false
+    } else {
+        return None;
+    };
+    let unit = if little { u16::from_le_bytes } else { u16::from_be_bytes };
+    let mut units: Vec<u16> = sample.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])).collect();
+    // A pair cut in half by the sample boundary isn't a lone surrogate
+    if bytes.len() > sample.len() && units.last().is_some_and(|u| (0xD800..0xDC00).contains(u)) {
+        units.pop();
+    }
+    let mut chars = Vec::with_capacity(units.len());
+    for c in char::decode_utf16(units) {
+        chars.push(c.ok()?);
+    }
+    mostly_printable(chars.into_iter()).then_some(little)
+}
+
+// Latin-1 except for 0x80-0x9F, where Windows-1252 puts punctuation and a few letters
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž', '\u{8F}',
+        '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}', 'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[(byte - 0x80) as usize],
+        _ => byte as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn png_is_binary() {
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR\x00\x00\x00\x10\x00\x00\x00\x10\x08\x06\x00\x00\x00\x1f\xf3\xffa".to_vec();
+        let mut idat = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        idat.write_all(&(0..4096u32).map(|i| (i * 7 % 251) as u8).collect::<Vec<_>>()).unwrap();
+        png.extend(idat.finish().unwrap());
+        assert!(decode(&png).is_none());
+    }
+
+    #[test]
+    fn gzip_is_binary() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&"fn main() {}\n".repeat(200).into_bytes()).unwrap();
+        let bytes = gz.finish().unwrap();
+        assert!(decode(&bytes).is_none());
+        // Without the header zeros the compressed body still has too many control bytes
+        let body = bytes[10..].iter().copied().filter(|&b| b != 0).collect::<Vec<_>>();
+        assert!(decode(&body).is_none());
+    }
+
+    #[test]
+    fn small_integer_arrays_are_not_utf16() {
+        let le: Vec<u8> = (0..512u16).map(|i| i % 32).flat_map(u16::to_le_bytes).collect();
+        let be: Vec<u8> = (0..512u16).map(|i| i % 32).flat_map(u16::to_be_bytes).collect();
+        assert!(decode(&le).is_none());
+        assert!(decode(&be).is_none());
+    }
+
+    #[test]
+    fn lone_surrogates_are_not_utf16() {
+        let mut bytes = utf16le(&"name = value\n".repeat(10));
+        bytes.extend([0x00, 0xD8, 0x41, 0x00]);
+        assert!(decode(&bytes).is_none());
+    }
+
+    #[test]
+    fn utf16_with_and_without_bom() {
+        let text = "Hello, wörld\r\nzweite Zeile\n";
+        let mut bom = b"\xFF\xFE".to_vec();
+        bom.extend(utf16le(text));
+        assert_eq!(decode(&bom), Some((text.to_string(), Some("UTF-16LE"))));
+        assert_eq!(decode(&utf16le(text)), Some((text.to_string(), Some("UTF-16LE"))));
+        let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode(&be), Some((text.to_string(), Some("UTF-16BE"))));
+    }
+
+    #[test]
+    fn utf16_sample_boundary_inside_a_surrogate_pair() {
+        // 511 units of ASCII then an emoji: the sample ends on its high surrogate
+        let text = format!("{}😀 tail\n", "a".repeat(511));
+        assert_eq!(decode(&utf16le(&text)), Some((text, Some("UTF-16LE"))));
+    }
+
+    #[test]
+    fn utf8_and_windows_1252() {
+        assert_eq!(decode("grüße\n".as_bytes()), Some(("grüße\n".to_string(), None)));
+        assert_eq!(decode(b"caf\xe9 \x93quoted\x94\n"), Some(("café “quoted”\n".to_string(), Some("Windows-1252"))));
+        // A truncated multi-byte char at the end is still UTF-8
+        assert_eq!(decode(b"ab\xc3").map(|(_, enc)| enc), Some(None));
+    }
+
+    #[test]
+    fn control_heavy_high_bytes_are_binary() {
+        let bytes: Vec<u8> = (0..600u32).map(|i| [0x01, 0x02, 0xE9, 0x7F, 0x1C, 0x9B][i as usize % 6]).collect();
+        assert!(decode(&bytes).is_none());
+    }
+}
@@ -0,0 +1,20 @@
+// FNV-1a (64-bit), for the short ids that have to stay stable across builds and
+// platforms, which std's hasher doesn't promise: clone cache keys, selection state
+// files, secret fingerprints and the dedupe index.
+
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |h, &b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference values from the FNV authors' test suite
+    #[test]
+    fn known_vectors() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+}
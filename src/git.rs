@@ -0,0 +1,1250 @@
+use anyhow::{Result, Context};
+use colored::*;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::gitdb::{self, Oid};
+
+// GIT INTELLIGENCE
+// Diff mode (`--diff`, `--diff-ref`, `--staged`, `--commits`, `--patch`) reads the
+// repository through `gitdb` and needs no `git` binary. The history walks behind --since
+// and --git-meta, blame, compare, hook install and LFS are thin wrappers over the `git`
+// CLI. Every CLI call reports failures the same way.
+//
+// Everything runs against the repository of the scan target, never the working directory,
+// and repo-root-relative paths are mapped back onto the target as given, so
+// `gimtex ../other --diff` means what it says.
+
+pub struct Repo {
+    dir: PathBuf,
+    // The target's location inside the repository (what `git rev-parse --show-prefix` prints), e.g. "src/"
+    prefix: String,
+    store: gitdb::Store,
+}
+
+impl Repo {
+    pub fn open(target: &Path) -> Result<Self> {
+        let dir = if target.is_file() {
+            target.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new(".")).to_path_buf()
+        } else {
+            target.to_path_buf()
+        };
+        let Some(store) = gitdb::Store::discover(&dir)? else {
+            anyhow::bail!("{} is not inside a git repository", target.display());
+        };
+        let canonical = dir.canonicalize().with_context(|| format!("Failed to resolve {}", dir.display()))?;
+        let inside = canonical.strip_prefix(&store.root).unwrap_or(Path::new(""));
+        let prefix = inside.components().map(|c| format!("{}/", c.as_os_str().to_string_lossy())).collect();
+        Ok(Self { dir, prefix, store })
+    }
+
+    // Honors core.hooksPath and linked worktrees
+    pub fn hooks_dir(&self) -> Option<PathBuf> {
+        let hooks = query(&self.dir, &["rev-parse", "--git-path", "hooks"])?;
+        Some(self.dir.join(hooks.trim()))
+    }
+
+    // Initialized submodules at or below the target, nested ones included, as (path from
+    // the target, checkout path). Uninitialized ones are an empty directory without `.git`.
+    pub fn submodules(&self) -> Vec<(String, PathBuf)> {
+        let Ok(index) = self.store.index() else { return Vec::new() };
+        let mut found = Vec::new();
+        for gitlink in index.entries.iter().filter(|e| e.entry.mode == gitdb::GITLINK) {
+            let Some(name) = gitlink.path.strip_prefix(self.prefix.as_str()) else { continue };
+            let checkout = self.dir.join(name);
+            if !checkout.join(".git").exists() {
+                continue;
+            }
+            let nested = Repo::open(&checkout).map(|repo| repo.submodules()).unwrap_or_default();
+            found.push((name.to_string(), checkout));
+            found.extend(nested.into_iter().map(|(inner, path)| (format!("{}/{}", name, inner), path)));
+        }
+        found
+    }
+
+    // Files committed to since `since` (any git date, e.g. "1209600 seconds ago") plus
+    // everything currently modified or untracked, which is recent by definition
+    pub fn touched_since(&self, since: &str) -> Result<Vec<PathBuf>> {
+        let since = format!("--since={}", since);
+        let output = self.git(&["log", "-z", "--name-only", "--format=", &since])?;
+        let mut names: Vec<String> = output.split('\0').filter(|n| !n.is_empty()).map(str::to_string).collect();
+        names.extend(status_files(self, &ChangeSet { staged: true, unstaged: true, untracked: true })?);
+        Ok(existing(self, names))
+    }
+
+    // The newest commit touching each file, from one `git log` walk (newest first,
+    // so the first sighting of a path wins). Renames are not followed.
+    pub fn last_commits(&self) -> Result<HashMap<PathBuf, LastCommit>> {
+        // With -z every name ends in NUL, and the %x00 opening each header leaves an empty
+        // field in front of it; the first name follows the header's newline
+        let output = self.git(&["log", "-z", "--no-color", "--name-only", "--format=%x00%h%x09%an%x09%ae%x09%as"])?;
+        let mut commits = HashMap::new();
+        let mut fields = output.split('\0');
+        let mut commit: Option<LastCommit> = None;
+        let mut first = false;
+        while let Some(field) = fields.next() {
+            if field.is_empty() {
+                let header = fields.next().unwrap_or_default();
+                let mut parts = header.splitn(4, '\t').map(|f| f.to_string());
+                let mut part = || parts.next().unwrap_or_default();
+                commit = Some(LastCommit { sha: part(), author: part(), email: part(), date: part() });
+                first = true;
+                continue;
+            }
+            let name = if first { field.strip_prefix('\n').unwrap_or(field) } else { field };
+            first = false;
+            if let (Some(commit), Some(path)) = (&commit, self.resolve(name)) {
+                commits.entry(path).or_insert_with(|| commit.clone());
+            }
+        }
+        Ok(commits)
+    }
+
+    // `git diff --numstat A B`, one entry per file (-z keeps non-ASCII names unquoted)
+    pub fn numstat(&self, from: &str, to: &str) -> Result<Vec<FileStat>> {
+        let output = self.git(&["diff", "-z", "--numstat", "--no-renames", from, to])?;
+        Ok(output
+            .split('\0')
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let (added, removed, name) = (fields.next()?, fields.next()?, fields.next()?);
+                Some(FileStat { added: added.parse().ok(), removed: removed.parse().ok(), name: name.to_string() })
+            })
+            .collect())
+    }
+
+    // Writes `rev:name` (repo-root-relative) to `dest`; false when the file doesn't exist at `rev`
+    pub fn export(&self, rev: &str, name: &str, dest: &Path) -> Result<bool> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(["show", &format!("{}:{}", rev, name)])
+            .output()
+            .context("Failed to execute git")?;
+        if !output.status.success() {
+            return Ok(false);
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(dest, &output.stdout).with_context(|| format!("Failed to write {}", dest.display()))?;
+        Ok(true)
+    }
+
+    // Staged files whose working copy has changed since `git add`, repo-root-relative
+    pub fn modified_after_staging(&self) -> Result<Vec<String>> {
+        let index = self.store.index()?;
+        let unstaged: HashSet<String> = self.store.diff_worktree(&index)?.into_iter().map(|c| c.path).collect();
+        let staged = self.store.diff_index(self.store.head_tree()?.as_ref(), &index)?;
+        Ok(staged.into_iter().map(|c| c.path).filter(|name| unstaged.contains(name)).collect())
+    }
+
+    // The index version of every staged file (what the commit will contain), keyed like
+    // `changed_files` paths. Deletions and gitlinks are skipped.
+    pub fn staged_blobs(&self) -> Result<HashMap<PathBuf, Vec<u8>>> {
+        let index = self.store.index()?;
+        let mut blobs = HashMap::new();
+        for change in self.store.diff_index(self.store.head_tree()?.as_ref(), &index)? {
+            let Some(staged) = change.new.filter(|e| e.mode != gitdb::GITLINK) else { continue };
+            if let Some(path) = self.resolve(&change.path) {
+                blobs.insert(path, self.store.blob(&staged.id)?);
+            }
+        }
+        Ok(blobs)
+    }
+
+    // Repo-root-relative name -> path under the target; None when it lies outside the target
+    fn resolve(&self, name: &str) -> Option<PathBuf> {
+        name.strip_prefix(self.prefix.as_str()).map(|rest| self.dir.join(rest))
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(args)
+            .output()
+            .context("Failed to execute git")?;
+
+        if !output.status.success() {
+            eprintln!("{} Git command failed: git {}", "[X]".red().bold(), args.join(" "));
+            eprintln!("{}", String::from_utf8_lossy(&output.stderr).trim_end());
+            anyhow::bail!("Git command failed");
+        }
+
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+// Best-effort query against the repository containing `dir`: no git, no repo or a
+// failing command all just mean "no data", without the failure report of `git()`.
+fn query(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// What `--diff` / `--diff-ref` compare against
+pub enum DiffSpec {
+    // Working tree vs a commit (`--diff`, `--diff=<rev>`)
+    Commit(String),
+    // Two-point or three-dot ranges (`--diff=A..B`, `--diff=A...B`)
+    Range(String),
+    // Everything the current branch changed since it forked from a base (`--diff-ref origin/main`)
+    Base(String),
+    // Only what is in the index, i.e. about to be committed (`--staged`)
+    Staged,
+    // Every file touched by a commit or commit range, at its current contents (`--commits`)
+    History(String),
+}
+
+impl DiffSpec {
+    pub fn describe(&self) -> String {
+        match self {
+            DiffSpec::Commit(rev) => format!("working tree vs {}", rev),
+            DiffSpec::Range(range) => range.clone(),
+            DiffSpec::Base(base) => format!("changes since {}", base),
+            DiffSpec::Staged => "staged changes".to_string(),
+            DiffSpec::History(revs) => format!("files touched by {}", revs),
+        }
+    }
+}
+
+// Line counts are None for binary files
+pub struct FileStat {
+    pub added: Option<usize>,
+    pub removed: Option<usize>,
+    pub name: String,
+}
+
+#[derive(Clone)]
+pub struct LastCommit {
+    pub sha: String,
+    pub author: String,
+    pub email: String,
+    // Author date, YYYY-MM-DD
+    pub date: String,
+}
+
+// Which working-copy categories plain `--diff` collects (like `git status`)
+pub struct ChangeSet {
+    pub staged: bool,
+    pub unstaged: bool,
+    pub untracked: bool,
+}
+
+pub fn changed_files(repo: &Repo, spec: &DiffSpec, changes: &ChangeSet) -> Result<Vec<PathBuf>> {
+    let store = &repo.store;
+    let mut names: Vec<String> = match spec {
+        DiffSpec::Commit(rev) if rev == "HEAD" => return Ok(existing(repo, status_files(repo, changes)?)),
+        DiffSpec::Commit(rev) => paths(store.diff_tree_worktree(Some(&tree(store, rev)?), &store.index()?)?),
+        DiffSpec::Range(range) => {
+            let (old, new) = range_trees(store, range)?;
+            paths(store.diff_trees(Some(&old), Some(&new))?)
+        }
+        DiffSpec::Base(base) => {
+            // Merge-base diff: the branch's own changes (committed or not), not what landed on base meanwhile
+            let fork_point = store.commit(&fork_point(store, base)?)?.tree;
+            paths(store.diff_tree_worktree(Some(&fork_point), &store.index()?)?)
+        }
+        // Files deleted from the working tree after staging are still about to be committed
+        DiffSpec::Staged => {
+            let staged = store.diff_index(store.head_tree()?.as_ref(), &store.index()?)?;
+            let staged = staged.into_iter().filter(|c| c.new.is_some());
+            return Ok(staged.filter_map(|c| repo.resolve(&c.path)).filter(|p| !p.is_dir()).collect());
+        }
+        DiffSpec::History(revs) => {
+            let mut names = Vec::new();
+            for (_, commit) in history(store, revs)? {
+                // Like `git log`, a merge lists no files of its own
+                if commit.parents.len() > 1 {
+                    continue;
+                }
+                let parent = commit.parents.first().map(|p| store.commit(p)).transpose()?.map(|p| p.tree);
+                names.extend(paths(store.diff_trees(parent.as_ref(), Some(&commit.tree))?));
+            }
+            names.sort();
+            names.dedup();
+            names
+        }
+    };
+
+    // New files never show up in a diff; append them when asked for
+    if changes.untracked && !matches!(spec, DiffSpec::Staged) {
+        let only_untracked = ChangeSet { staged: false, unstaged: false, untracked: true };
+        names.extend(status_files(repo, &only_untracked)?);
+    }
+
+    Ok(existing(repo, names))
+}
+
+fn paths(changes: Vec<gitdb::Change>) -> Vec<String> {
+    changes.into_iter().map(|c| c.path).collect()
+}
+
+fn tree(store: &gitdb::Store, rev: &str) -> Result<Oid> {
+    Ok(store.commit(&store.resolve(rev)?)?.tree)
+}
+
+// A range side left out (`A..`, `..B`) means HEAD
+fn side(store: &gitdb::Store, rev: &str) -> Result<Oid> {
+    store.resolve(if rev.is_empty() { "HEAD" } else { rev })
+}
+
+// The trees `git diff` compares for a range: A against B for `A..B`, the merge base
+// against B for `A...B`
+fn range_trees(store: &gitdb::Store, range: &str) -> Result<(Oid, Oid)> {
+    let (old, new) = match range.split_once("...") {
+        Some((a, b)) => {
+            let (a, b) = (side(store, a)?, side(store, b)?);
+            (store.merge_base(a, b)?.with_context(|| format!("{} has no merge base", range))?, b)
+        }
+        None => {
+            let (a, b) = range.split_once("..").with_context(|| format!("Not a range: {}", range))?;
+            (side(store, a)?, side(store, b)?)
+        }
+    };
+    Ok((store.commit(&old)?.tree, store.commit(&new)?.tree))
+}
+
+// Where the current branch forked from `base`
+fn fork_point(store: &gitdb::Store, base: &str) -> Result<Oid> {
+    let (base_id, head) = (store.resolve(base)?, store.resolve("HEAD")?);
+    store.merge_base(base_id, head)?.with_context(|| format!("HEAD has no merge base with {}", base))
+}
+
+// The commits of `--commits`: a range as `git log` reads it, or a lone commit on its own (`X^!`)
+fn history(store: &gitdb::Store, revs: &str) -> Result<Vec<(Oid, gitdb::Commit)>> {
+    if let Some((a, b)) = revs.split_once("...") {
+        let (a, b) = (side(store, a)?, side(store, b)?);
+        let bases: Vec<Oid> = store.merge_base(a, b)?.into_iter().collect();
+        return store.walk(&[a, b], &bases);
+    }
+    if let Some((a, b)) = revs.split_once("..") {
+        return store.walk(&[side(store, b)?], &[side(store, a)?]);
+    }
+    let id = store.resolve(revs)?;
+    store.walk(&[id], &store.commit(&id)?.parents)
+}
+
+// `--commits` context header: one "hash subject" line per commit, newest first
+pub fn commit_subjects(repo: &Repo, revs: &str) -> Result<Vec<String>> {
+    let commits = history(&repo.store, revs)?;
+    Ok(commits.iter().map(|(id, commit)| format!("{} {}", short_id(id), commit.subject)).collect())
+}
+
+// Seven hex digits, git's default abbreviation
+fn short_id(id: &Oid) -> String {
+    crate::sha1::hex(id)[..7].to_string()
+}
+
+fn lines(output: String) -> Vec<String> {
+    output.lines().map(|l| l.to_string()).collect()
+}
+
+// Deleted files still show up in name lists; only existing files have content to extract
+fn existing(repo: &Repo, names: Vec<String>) -> Vec<PathBuf> {
+    names.iter().filter_map(|name| repo.resolve(name)).filter(|p| p.is_file()).collect()
+}
+
+// What `git status` reports, by category: staged (index vs HEAD), unstaged (working tree
+// vs index) and untracked. Conflicted paths count as both staged and unstaged.
+fn status_files(repo: &Repo, changes: &ChangeSet) -> Result<Vec<String>> {
+    let store = &repo.store;
+    let index = store.index()?;
+    let mut files = Vec::new();
+    if changes.staged {
+        files.extend(paths(store.diff_index(store.head_tree()?.as_ref(), &index)?));
+    }
+    if changes.unstaged {
+        files.extend(paths(store.diff_worktree(&index)?));
+    }
+    if changes.untracked {
+        files.extend(store.untracked(&index)?);
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+// PATCH MODE
+// The spec's changes as `git diff` prints them, one patch per file, built from the stored
+// blobs and the working tree. Deletions, binary files and mode-only changes have no new
+// lines to show and get no patch. Where several edit scripts are equally short, a hunk can
+// pair lines differently from git's; it still applies the same.
+pub fn patches(repo: &Repo, spec: &DiffSpec, changes: &ChangeSet, context: usize) -> Result<HashMap<PathBuf, String>> {
+    let store = &repo.store;
+    let index = store.index()?;
+    // The new side is either the working tree (read from disk) or stored blobs
+    let (found, on_disk) = match spec {
+        // Plain --diff: index vs HEAD for staged, worktree vs index for unstaged, both => worktree vs HEAD
+        DiffSpec::Commit(rev) if rev == "HEAD" => match (changes.staged, changes.unstaged) {
+            (true, false) => (store.diff_index(store.head_tree()?.as_ref(), &index)?, false),
+            (false, true) => (store.diff_worktree(&index)?, true),
+            _ => (store.diff_tree_worktree(store.head_tree()?.as_ref(), &index)?, true),
+        },
+        DiffSpec::Commit(rev) => (store.diff_tree_worktree(Some(&tree(store, rev)?), &index)?, true),
+        DiffSpec::Range(range) => {
+            let (old, new) = range_trees(store, range)?;
+            (store.diff_trees(Some(&old), Some(&new))?, false)
+        }
+        DiffSpec::Base(base) => {
+            let fork_point = store.commit(&fork_point(store, base)?)?.tree;
+            (store.diff_tree_worktree(Some(&fork_point), &index)?, true)
+        }
+        DiffSpec::Staged => (store.diff_index(store.head_tree()?.as_ref(), &index)?, false),
+        DiffSpec::History(revs) if revs.contains("..") => {
+            let (old, new) = range_trees(store, revs)?;
+            (store.diff_trees(Some(&old), Some(&new))?, false)
+        }
+        // One commit against its first parent
+        DiffSpec::History(rev) => {
+            let commit = store.commit(&store.resolve(rev)?)?;
+            let parent = commit.parents.first().map(|p| store.commit(p)).transpose()?.map(|p| p.tree);
+            (store.diff_trees(parent.as_ref(), Some(&commit.tree))?, false)
+        }
+    };
+
+    let mut patches = HashMap::new();
+    for change in found {
+        let Some(new) = change.new.filter(|e| e.mode != gitdb::GITLINK) else { continue };
+        let Some(path) = repo.resolve(&change.path) else { continue };
+        let old = change.old.filter(|e| e.mode != gitdb::GITLINK);
+        let before = old.map(|e| store.blob(&e.id)).transpose()?.unwrap_or_default();
+        let after = if on_disk { gitdb::worktree_bytes(&store.root.join(&change.path), new.mode)? } else { store.blob(&new.id)? };
+        if let Some(patch) = file_patch(&change.path, old, new, &before, &after, context) {
+            patches.insert(path, patch);
+        }
+    }
+    Ok(patches)
+}
+
+// One file's `diff --git` block; None when there are no lines to show
+fn file_patch(name: &str, old: Option<gitdb::Entry>, new: gitdb::Entry, before: &[u8], after: &[u8], context: usize) -> Option<String> {
+    // git's binary test: a NUL in the first 8000 bytes of either side
+    let binary = |bytes: &[u8]| bytes[..bytes.len().min(8000)].contains(&0);
+    if before == after || binary(before) || binary(after) {
+        return None;
+    }
+    let mut patch = format!("diff --git a/{} b/{}\n", name, name);
+    match old {
+        None => patch.push_str(&format!("new file mode {:06o}\nindex 0000000..{}\n--- /dev/null\n", new.mode, short_id(&new.id))),
+        Some(old) if old.mode != new.mode => patch.push_str(&format!(
+            "old mode {:06o}\nnew mode {:06o}\nindex {}..{}\n--- a/{}\n",
+            old.mode,
+            new.mode,
+            short_id(&old.id),
+            short_id(&new.id),
+            name
+        )),
+        Some(old) => patch.push_str(&format!("index {}..{} {:06o}\n--- a/{}\n", short_id(&old.id), short_id(&new.id), new.mode, name)),
+    }
+    patch.push_str(&format!("+++ b/{}\n", name));
+    patch.push_str(&hunks(before, after, context));
+    Some(patch)
+}
+
+// Unified hunks with `context` lines around each change; changes at most twice that apart
+// share a hunk. Each header ends with the nearest line above the hunk that starts with a
+// letter, `_` or `$`, git's default function context.
+fn hunks(before: &[u8], after: &[u8], context: usize) -> String {
+    let a: Vec<&[u8]> = before.split_inclusive(|&b| b == b'\n').collect();
+    let b: Vec<&[u8]> = after.split_inclusive(|&b| b == b'\n').collect();
+
+    let runs = edit_runs(&a, &b);
+
+    let mut out = String::new();
+    let mut first = 0;
+    while first < runs.len() {
+        let mut last = first;
+        while last + 1 < runs.len() && runs[last + 1].0 - runs[last].1 <= 2 * context {
+            last += 1;
+        }
+        let a_start = runs[first].0.saturating_sub(context);
+        let b_start = runs[first].2 - (runs[first].0 - a_start);
+        let a_end = (runs[last].1 + context).min(a.len());
+        let b_end = runs[last].3 + (a_end - runs[last].1);
+        out.push_str(&format!(
+            "@@ -{} +{} @@{}\n",
+            hunk_range(a_start, a_end - a_start),
+            hunk_range(b_start, b_end - b_start),
+            function_line(&a[..a_start])
+        ));
+        let mut at = a_start;
+        for &(a_from, a_to, b_from, b_to) in &runs[first..=last] {
+            a[at..a_from].iter().for_each(|line| push_line(&mut out, ' ', line));
+            a[a_from..a_to].iter().for_each(|line| push_line(&mut out, '-', line));
+            b[b_from..b_to].iter().for_each(|line| push_line(&mut out, '+', line));
+            at = a_to;
+        }
+        a[at..a_end].iter().for_each(|line| push_line(&mut out, ' ', line));
+        first = last + 1;
+    }
+    out
+}
+
+// "start,count", 1-based; an empty range names the line before it, a single line drops the count
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+fn function_line(above: &[&[u8]]) -> String {
+    let heading = above.iter().rev().find(|line| line.first().is_some_and(|&c| c.is_ascii_alphabetic() || c == b'_' || c == b'$'));
+    match heading {
+        // git keeps at most 80 bytes of it
+        Some(line) => format!(" {}", String::from_utf8_lossy(&line[..line.len().min(80)]).trim_end()),
+        None => String::new(),
+    }
+}
+
+fn push_line(out: &mut String, marker: char, line: &[u8]) {
+    out.push(marker);
+    out.push_str(&String::from_utf8_lossy(line));
+    if !line.ends_with(b"\n") {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+// Changed runs between the shared lines, as (a_start, a_end, b_start, b_end). The lines a
+// shortest edit script keeps come from Myers' linear-space refinement (trim the shared
+// ends, split where the forward and backward searches meet, recurse); the changes are then
+// slid into place the way git does it.
+fn edit_runs<'a>(a: &[&'a [u8]], b: &[&'a [u8]]) -> Vec<(usize, usize, usize, usize)> {
+    let mut ids: HashMap<&[u8], usize> = HashMap::new();
+    let mut intern = |line: &'a [u8]| {
+        let next = ids.len();
+        *ids.entry(line).or_insert(next)
+    };
+    let mut old = Side { ids: a.iter().map(|line| intern(line)).collect(), lines: a, changed: vec![true; a.len()] };
+    let mut new = Side { ids: b.iter().map(|line| intern(line)).collect(), lines: b, changed: vec![true; b.len()] };
+    let mut matched = Vec::new();
+    match_lines(&old.ids, &new.ids, (0, 0), &mut matched);
+    for (x, y) in matched {
+        old.changed[x] = false;
+        new.changed[y] = false;
+    }
+    compact(&mut old, &mut new);
+    compact(&mut new, &mut old);
+
+    // Unchanged lines pair up in order, so both sides step past them together
+    let mut runs = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    while x < a.len() || y < b.len() {
+        if old.flag(x) || new.flag(y) {
+            let (from_x, from_y) = (x, y);
+            while old.flag(x) {
+                x += 1;
+            }
+            while new.flag(y) {
+                y += 1;
+            }
+            runs.push((from_x, x, from_y, y));
+        } else {
+            x += 1;
+            y += 1;
+        }
+    }
+    runs
+}
+
+fn match_lines(a: &[usize], b: &[usize], at: (usize, usize), matched: &mut Vec<(usize, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    matched.extend((0..prefix).map(|i| (at.0 + i, at.1 + i)));
+    let (a, b, at) = (&a[prefix..], &b[prefix..], (at.0 + prefix, at.1 + prefix));
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    if !a.is_empty() && !b.is_empty() {
+        if let Some((x, y)) = middle(a, b).filter(|&split| split != (0, 0) && split != (a.len(), b.len())) {
+            match_lines(&a[..x], &b[..y], at, matched);
+            match_lines(&a[x..], &b[y..], (at.0 + x, at.1 + y), matched);
+        }
+    }
+    matched.extend((0..suffix).map(|i| (at.0 + a.len() + i, at.1 + b.len() + i)));
+}
+
+// A point on a shortest edit path, where the furthest-reaching forward and backward paths
+// first overlap. Diagonals that run off the edit graph are dropped from the search.
+fn middle(a: &[usize], b: &[usize]) -> Option<(usize, usize)> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m + 1) / 2;
+    let width = 2 * max as usize + 2;
+    let (mut forward, mut backward) = (vec![-1isize; width], vec![-1isize; width]);
+    forward[max as usize + 1] = 0;
+    backward[max as usize + 1] = 0;
+    let delta = n - m;
+    // With an odd delta the paths meet on a forward step, with an even one on a backward step
+    let odd = delta % 2 != 0;
+    let (mut trim_forward, mut trim_backward) = ((0, 0), (0, 0));
+    for d in 0..max {
+        let mut k = -d + trim_forward.0;
+        while k <= d - trim_forward.1 {
+            let i = (max + k) as usize;
+            let mut x = if k == -d || (k != d && forward[i - 1] < forward[i + 1]) { forward[i + 1] } else { forward[i - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[i] = x;
+            if x > n {
+                trim_forward.1 += 2;
+            } else if y > m {
+                trim_forward.0 += 2;
+            } else if odd {
+                let j = max + delta - k;
+                if (0..width as isize).contains(&j) && backward[j as usize] != -1 && x >= n - backward[j as usize] {
+                    return Some((x as usize, y as usize));
+                }
+            }
+            k += 2;
+        }
+
+        let mut k = -d + trim_backward.0;
+        while k <= d - trim_backward.1 {
+            let i = (max + k) as usize;
+            let mut x = if k == -d || (k != d && backward[i - 1] < backward[i + 1]) { backward[i + 1] } else { backward[i - 1] + 1 };
+            let mut y = x - k;
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[i] = x;
+            if x > n {
+                trim_backward.1 += 2;
+            } else if y > m {
+                trim_backward.0 += 2;
+            } else if !odd {
+                let j = max + delta - k;
+                if (0..width as isize).contains(&j) && forward[j as usize] != -1 && forward[j as usize] >= n - x {
+                    let x = forward[j as usize];
+                    return Some((x as usize, (x - (j - max)) as usize));
+                }
+            }
+            k += 2;
+        }
+    }
+    None
+}
+
+// One file of a diff: its lines, their interned ids and which ones the edit script changes
+struct Side<'a> {
+    lines: &'a [&'a [u8]],
+    ids: Vec<usize>,
+    changed: Vec<bool>,
+}
+
+// A run of changed lines, empty between two unchanged ones
+struct Group {
+    start: usize,
+    end: usize,
+}
+
+// Slides past this many positions are not scored
+const MAX_SLIDING: usize = 100;
+
+impl Side<'_> {
+    fn flag(&self, line: usize) -> bool {
+        self.changed.get(line).copied().unwrap_or(false)
+    }
+
+    fn first(&self) -> Group {
+        let mut end = 0;
+        while self.flag(end) {
+            end += 1;
+        }
+        Group { start: 0, end }
+    }
+
+    fn next(&self, group: &mut Group) -> bool {
+        if group.end >= self.changed.len() {
+            return false;
+        }
+        group.start = group.end + 1;
+        group.end = group.start;
+        while self.flag(group.end) {
+            group.end += 1;
+        }
+        true
+    }
+
+    fn previous(&self, group: &mut Group) -> bool {
+        if group.start == 0 {
+            return false;
+        }
+        group.end = group.start - 1;
+        group.start = group.end;
+        while group.start > 0 && self.changed[group.start - 1] {
+            group.start -= 1;
+        }
+        true
+    }
+
+    // Moves a group one line down when the line after it equals its first line, taking
+    // in the group it then touches
+    fn slide_down(&mut self, group: &mut Group) -> bool {
+        if group.end >= self.ids.len() || self.ids[group.start] != self.ids[group.end] {
+            return false;
+        }
+        self.changed[group.start] = false;
+        self.changed[group.end] = true;
+        group.start += 1;
+        group.end += 1;
+        while self.flag(group.end) {
+            group.end += 1;
+        }
+        true
+    }
+
+    fn slide_up(&mut self, group: &mut Group) -> bool {
+        if group.start == 0 || self.ids[group.start - 1] != self.ids[group.end - 1] {
+            return false;
+        }
+        group.start -= 1;
+        group.end -= 1;
+        self.changed[group.start] = true;
+        self.changed[group.end] = false;
+        while group.start > 0 && self.changed[group.start - 1] {
+            group.start -= 1;
+        }
+        true
+    }
+}
+
+// xdiff's `xdl_change_compact`: each group of changes slides as far up and down as equal
+// lines allow, merging with the groups it meets, then settles level with a change on the
+// other side if it can, or else where the indent heuristic scores best
+fn compact(side: &mut Side, other: &mut Side) {
+    let (mut group, mut other_group) = (side.first(), other.first());
+    loop {
+        if group.end != group.start {
+            let (mut earliest_end, mut matching_other);
+            loop {
+                let size = group.end - group.start;
+                matching_other = false;
+                while side.slide_up(&mut group) {
+                    other.previous(&mut other_group);
+                }
+                earliest_end = group.end;
+                if other_group.end > other_group.start {
+                    matching_other = true;
+                }
+                while side.slide_down(&mut group) {
+                    other.next(&mut other_group);
+                    if other_group.end > other_group.start {
+                        matching_other = true;
+                    }
+                }
+                if size == group.end - group.start {
+                    break;
+                }
+            }
+
+            if group.end == earliest_end {
+                // Nowhere to slide
+            } else if matching_other {
+                while other_group.end == other_group.start {
+                    side.slide_up(&mut group);
+                    other.previous(&mut other_group);
+                }
+            } else {
+                let size = group.end - group.start;
+                let lowest = earliest_end.max((group.end - size).saturating_sub(1)).max(group.end.saturating_sub(MAX_SLIDING));
+                let mut best: Option<(usize, (i32, i32))> = None;
+                for shift in lowest..=group.end {
+                    let score = add_split(split_at(side.lines, shift), add_split(split_at(side.lines, shift - size), (0, 0)));
+                    if best.is_none_or(|(_, best)| compare_scores(score, best) <= 0) {
+                        best = Some((shift, score));
+                    }
+                }
+                let best = best.map_or(group.end, |(shift, _)| shift);
+                while group.end > best {
+                    side.slide_up(&mut group);
+                    other.previous(&mut other_group);
+                }
+            }
+        }
+        if !side.next(&mut group) {
+            break;
+        }
+        other.next(&mut other_group);
+    }
+}
+
+// The indent heuristic's view of a split point: the indent there (None for a blank line
+// or the end of the file) and of the nearest non-blank lines around it
+struct Split {
+    end_of_file: bool,
+    indent: Option<i32>,
+    pre_blank: i32,
+    pre_indent: Option<i32>,
+    post_blank: i32,
+    post_indent: Option<i32>,
+}
+
+// Blank lines counted around a split before they stop mattering
+const MAX_BLANKS: i32 = 20;
+
+// Columns, tabs to multiples of 8, capped at 200; None when the line is all whitespace
+fn indent(line: &[u8]) -> Option<i32> {
+    let mut columns = 0;
+    for &c in line {
+        match c {
+            b' ' => columns += 1,
+            b'\t' => columns += 8 - columns % 8,
+            b'\n' | b'\r' | 0x0b | 0x0c => {}
+            _ => return Some(columns),
+        }
+        if columns >= 200 {
+            return Some(200);
+        }
+    }
+    None
+}
+
+fn split_at(lines: &[&[u8]], split: usize) -> Split {
+    let mut measured = Split {
+        end_of_file: split >= lines.len(),
+        indent: lines.get(split).and_then(|line| indent(line)),
+        pre_blank: 0,
+        pre_indent: None,
+        post_blank: 0,
+        post_indent: None,
+    };
+    for line in lines[..split.min(lines.len())].iter().rev() {
+        measured.pre_indent = indent(line);
+        if measured.pre_indent.is_some() {
+            break;
+        }
+        measured.pre_blank += 1;
+        if measured.pre_blank == MAX_BLANKS {
+            measured.pre_indent = Some(0);
+            break;
+        }
+    }
+    for line in lines.iter().skip(split + 1) {
+        measured.post_indent = indent(line);
+        if measured.post_indent.is_some() {
+            break;
+        }
+        measured.post_blank += 1;
+        if measured.post_blank == MAX_BLANKS {
+            measured.post_indent = Some(0);
+            break;
+        }
+    }
+    measured
+}
+
+// (effective indent, penalty), with xdiff's weights: splits next to blank lines and
+// before less indented code are preferred
+fn add_split(split: Split, (mut effective_indent, mut penalty): (i32, i32)) -> (i32, i32) {
+    if split.pre_indent.is_none() && split.pre_blank == 0 {
+        penalty += 1;
+    }
+    if split.end_of_file {
+        penalty += 21;
+    }
+    let post_blank = if split.indent.is_none() { 1 + split.post_blank } else { 0 };
+    let total_blank = split.pre_blank + post_blank;
+    penalty += -30 * total_blank + 6 * post_blank;
+    let indent = split.indent.or(split.post_indent);
+    let blanks = total_blank != 0;
+    effective_indent += indent.unwrap_or(-1);
+    match (indent, split.pre_indent) {
+        (Some(indent), Some(pre)) if indent > pre => penalty += if blanks { 10 } else { -4 },
+        (Some(indent), Some(pre)) if indent < pre => {
+            let outdent = split.post_indent.is_some_and(|post| post > indent);
+            penalty += match (outdent, blanks) {
+                (true, true) => 17,
+                (true, false) => 24,
+                (false, true) => 17,
+                (false, false) => 23,
+            };
+        }
+        _ => {}
+    }
+    (effective_indent, penalty)
+}
+
+fn compare_scores(a: (i32, i32), b: (i32, i32)) -> i32 {
+    60 * (a.0.cmp(&b.0) as i32) + (a.1 - b.1)
+}
+
+// Untracked files have no diff yet; present them as an all-added patch so the format stays uniform.
+pub fn new_file_patch(path: &Path, content: &str) -> String {
+    let name = path.display();
+    let count = content.lines().count();
+    let mut patch = format!("new file {}\n--- /dev/null\n+++ b/{}\n@@ -0,0 +1,{} @@\n", name, name, count);
+    for line in content.lines() {
+        patch.push('+');
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    patch
+}
+
+// BLAME MODE
+// `git blame --line-porcelain` condensed to one "sha author" prefix per line.
+// Runs next to the file so clones and nested repos resolve on their own; untracked
+// or non-git files simply return None instead of reporting a failure per file.
+// git blames one path per process, so the calls are bounded instead of batched: the
+// worker pool is as wide as the machine and every blame re-reads the history.
+
+// `git blame` processes at once, whatever the size of the worker pool
+const BLAME_JOBS: usize = 4;
+
+static BLAME_SLOTS: (std::sync::Mutex<usize>, std::sync::Condvar) = (std::sync::Mutex::new(0), std::sync::Condvar::new());
+
+// "sha author" prefixes, the authors padded to one width
+pub fn blame_prefixes(entries: &[BlameLine]) -> Vec<String> {
+    let width = entries.iter().map(|e| e.author.chars().count()).max().unwrap_or(0);
+    entries.iter().map(|e| format!("{} {:<width$}", e.sha, e.author, width = width)).collect()
+}
+
+pub struct BlameLine {
+    pub sha: String,
+    pub author: String,
+    // Author time, seconds since the epoch
+    pub time: i64,
+    // The line as it is on disk
+    pub text: String,
+}
+
+// One entry per line of the file, in order
+pub fn blame_lines(path: &Path) -> Option<Vec<BlameLine>> {
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name()?.to_str()?;
+    let (lock, freed) = &BLAME_SLOTS;
+    let mut running = freed.wait_while(lock.lock().unwrap_or_else(|e| e.into_inner()), |n| *n >= BLAME_JOBS).unwrap_or_else(|e| e.into_inner());
+    *running += 1;
+    drop(running);
+    let text = query(dir, &["blame", "--line-porcelain", "--", name]);
+    *lock.lock().unwrap_or_else(|e| e.into_inner()) -= 1;
+    freed.notify_one();
+
+    let mut entries = Vec::new();
+    let mut sha = String::new();
+    let mut author = String::new();
+    let mut time = 0;
+    for line in text?.lines() {
+        if let Some(content) = line.strip_prefix('\t') {
+            entries.push(BlameLine { sha: sha.clone(), author: author.clone(), time, text: content.to_string() });
+        } else if let Some(name) = line.strip_prefix("author ") {
+            author = name.to_string();
+        } else if let Some(seconds) = line.strip_prefix("author-time ") {
+            time = seconds.trim().parse().unwrap_or(0);
+        } else if line.len() > 40 && line.as_bytes()[..40].iter().all(u8::is_ascii_hexdigit) {
+            sha = line[..7].to_string();
+        }
+    }
+    Some(entries)
+}
+
+// Edits past which two versions of a file count as unrelated
+const MAX_EDITS: usize = 2000;
+
+// For each line of `b`, the line of `a` it came from. Lines both sides share are matched
+// by a shortest edit script (Myers); the rest pair up in order within each changed run,
+// which is what a redacted or stripped line looks like. None past MAX_EDITS.
+pub fn line_origins(a: &[&str], b: &[&str]) -> Option<Vec<Option<usize>>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let off = MAX_EDITS as isize + 1;
+    let mut v = vec![0isize; 2 * MAX_EDITS + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut done = false;
+    for d in 0..=MAX_EDITS as isize {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[(off + k - 1) as usize] < v[(off + k + 1) as usize]) {
+                v[(off + k + 1) as usize]
+            } else {
+                v[(off + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(off + k) as usize] = x;
+            if x >= n && y >= m {
+                done = true;
+                break;
+            }
+        }
+        trace.push(v[(off - d) as usize..=(off + d) as usize].to_vec());
+        if done {
+            break;
+        }
+    }
+    if !done {
+        return None;
+    }
+
+    // Walk the script back from the end, collecting the diagonal (shared) moves
+    let mut matched = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let previous = &trace[d as usize - 1];
+        let at = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) { k + 1 } else { k - 1 };
+        let (prev_x, prev_y) = (at(prev_k), at(prev_k) - prev_k);
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            matched.push((x as usize, y as usize));
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        matched.push((x as usize, y as usize));
+    }
+    matched.reverse();
+
+    let mut origins = vec![None; b.len()];
+    let (mut next_a, mut next_b) = (0, 0);
+    for (ma, mb) in matched.into_iter().chain(std::iter::once((a.len(), b.len()))) {
+        for (offset, origin) in origins[next_b..mb].iter_mut().enumerate() {
+            *origin = (next_a + offset < ma).then_some(next_a + offset);
+        }
+        if mb < b.len() {
+            origins[mb] = Some(ma);
+        }
+        (next_a, next_b) = (ma + 1, mb + 1);
+    }
+    Some(origins)
+}
+
+pub fn remote_url(root: &Path) -> Option<String> {
+    query(root, &["remote", "get-url", "origin"]).map(|url| url.trim().to_string())
+}
+
+// Real content for an LFS pointer via `git lfs smudge` (pointer on stdin, object on stdout)
+pub fn lfs_smudge(path: &Path, pointer: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["lfs", "smudge", "--"])
+        .arg(path.file_name()?)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child.stdin.take()?.write_all(pointer).ok()?;
+    let output = child.wait_with_output().ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+// What is in flight in the repository around `root`, for the PROJECT CONTEXT block
+pub struct RepoSummary {
+    pub branch: String,
+    pub recent: Vec<String>,
+    pub dirty: usize,
+}
+
+pub fn summary(root: &Path, recent: usize) -> Option<RepoSummary> {
+    let mut branch = query(root, &["rev-parse", "--abbrev-ref", "HEAD"])?.trim().to_string();
+    if branch == "HEAD" {
+        let sha = query(root, &["rev-parse", "--short", "HEAD"]).unwrap_or_default();
+        branch = format!("detached at {}", sha.trim());
+    }
+    let limit = format!("-{}", recent);
+    let recent = query(root, &["log", "--no-color", &limit, "--format=%h %s"]).map(lines).unwrap_or_default();
+    let dirty = query(root, &["status", "--porcelain"]).map_or(0, |s| s.lines().count());
+    Some(RepoSummary { branch, recent, dirty })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").arg("-C").arg(dir).args(args).status().expect("git runs");
+        assert!(status.success(), "git {}", args.join(" "));
+    }
+
+    #[test]
+    fn line_origins_trace_deleted_merged_and_rewritten_lines() {
+        let a = ["one", "two", "three", "four", "five"];
+        // Unchanged
+        assert_eq!(line_origins(&a, &a), Some((0..5).map(Some).collect()));
+        // Lines dropped from the middle and the end
+        assert_eq!(line_origins(&a, &["one", "four"]), Some(vec![Some(0), Some(3)]));
+        // Three lines collapsed into one marker: it takes the first of them
+        assert_eq!(line_origins(&a, &["one", "[REDACTED]", "five"]), Some(vec![Some(0), Some(1), Some(4)]));
+        // Rewritten in place
+        assert_eq!(line_origins(&a, &["one", "", "three", "", "five"]), Some((0..5).map(Some).collect()));
+        // Text that was never in the file has no origin
+        assert_eq!(line_origins(&a, &["one", "two", "three", "four", "five", "[truncated]"]).unwrap()[5], None);
+        assert_eq!(line_origins(&[], &["new"]), Some(vec![None]));
+    }
+
+    #[test]
+    fn line_origins_give_up_on_unrelated_text() {
+        let a: Vec<String> = (0..MAX_EDITS).map(|i| format!("a{}", i)).collect();
+        let b: Vec<String> = (0..MAX_EDITS).map(|i| format!("b{}", i)).collect();
+        let (a, b): (Vec<&str>, Vec<&str>) = (a.iter().map(String::as_str).collect(), b.iter().map(String::as_str).collect());
+        assert_eq!(line_origins(&a, &b), None);
+    }
+
+    #[test]
+    fn staged_blobs_returns_index_contents_byte_for_byte() {
+        let dir = tempfile::tempdir().unwrap();
+        git(dir.path(), &["init", "-q"]);
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        let binary: Vec<u8> = vec![0, b'\n', 255, b'\n', b'\n', 7];
+        std::fs::write(dir.path().join("a.txt"), "staged\n").unwrap();
+        std::fs::write(dir.path().join("sub/b.bin"), &binary).unwrap();
+        std::fs::write(dir.path().join("empty"), "").unwrap();
+        git(dir.path(), &["add", "."]);
+        std::fs::write(dir.path().join("a.txt"), "working copy\n").unwrap();
+
+        let repo = Repo::open(dir.path()).unwrap();
+        let blobs = repo.staged_blobs().unwrap();
+        assert_eq!(blobs.len(), 3);
+        assert_eq!(blobs[&dir.path().join("a.txt")], b"staged\n");
+        assert_eq!(blobs[&dir.path().join("sub/b.bin")], binary);
+        assert!(blobs[&dir.path().join("empty")].is_empty());
+    }
+
+    fn commit(dir: &Path, message: &str) {
+        git(dir, &["-c", "user.name=Ada", "-c", "user.email=ada@example.com", "commit", "-q", "-m", message]);
+    }
+
+    fn cli(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git").arg("-C").arg(dir).args(args).output().expect("git runs");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    // Packed history (deltas included), the index and the working tree, read without git
+    // and checked against what git itself reports
+    #[test]
+    fn diff_mode_reads_the_repository_like_git() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        let body: String = (0..200).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(root.join("big.txt"), &body).unwrap();
+        std::fs::write(root.join("gone.txt"), "bye\n").unwrap();
+        git(root, &["add", "."]);
+        commit(root, "first");
+        for i in 0..3 {
+            std::fs::write(root.join("big.txt"), format!("{}edit {}\n", body, i)).unwrap();
+            git(root, &["add", "."]);
+            commit(root, &format!("edit {}", i));
+        }
+        git(root, &["gc", "-q", "--aggressive"]);
+
+        git(root, &["rm", "-q", "gone.txt"]);
+        std::fs::write(root.join("staged.txt"), "staged\n").unwrap();
+        git(root, &["add", "staged.txt"]);
+        std::fs::write(root.join("big.txt"), body.replace("line 100\n", "line one hundred\n")).unwrap();
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(root.join("debug.log"), "ignored\n").unwrap();
+        std::fs::write(root.join("new.txt"), "untracked\n").unwrap();
+
+        let repo = Repo::open(root).unwrap();
+        let all = ChangeSet { staged: true, unstaged: true, untracked: true };
+        let none = ChangeSet { staged: false, unstaged: false, untracked: false };
+        let mut files = changed_files(&repo, &DiffSpec::Commit("HEAD".to_string()), &all).unwrap();
+        files.sort();
+        assert_eq!(files, [".gitignore", "big.txt", "new.txt", "staged.txt"].map(|f| root.join(f)));
+        assert_eq!(changed_files(&repo, &DiffSpec::Staged, &all).unwrap(), [root.join("staged.txt")]);
+        assert_eq!(changed_files(&repo, &DiffSpec::Range("HEAD~3..HEAD".to_string()), &none).unwrap(), [root.join("big.txt")]);
+        assert_eq!(commit_subjects(&repo, "HEAD~2..HEAD").unwrap(), lines(cli(root, &["log", "--format=%h %s", "HEAD~2..HEAD"])));
+        assert_eq!(repo.modified_after_staging().unwrap(), Vec::<String>::new());
+
+        let patches = patches(&repo, &DiffSpec::Commit("HEAD".to_string()), &all, 3).unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[&root.join("big.txt")], cli(root, &["diff", "--no-color", "HEAD", "--", "big.txt"]));
+        assert_eq!(patches[&root.join("staged.txt")], cli(root, &["diff", "--no-color", "HEAD", "--", "staged.txt"]));
+    }
+
+    // Names git would C-quote in plain name lists ("caf\303\251.rs") come through every
+    // mode unquoted
+    #[test]
+    fn non_ascii_names_survive_every_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        std::fs::write(root.join("café.rs"), "fn a() {}\n").unwrap();
+        git(root, &["add", "."]);
+        commit(root, "first");
+        std::fs::write(root.join("naïve.rs"), "fn b() {}\n").unwrap();
+        git(root, &["add", "."]);
+        commit(root, "second");
+        std::fs::write(root.join("café.rs"), "fn a() { 1 }\n").unwrap();
+        std::fs::write(root.join("über.rs"), "fn c() {}\n").unwrap();
+        git(root, &["add", "über.rs"]);
+
+        let repo = Repo::open(root).unwrap();
+        let all = ChangeSet { staged: true, unstaged: true, untracked: true };
+        let none = ChangeSet { staged: false, unstaged: false, untracked: false };
+        let mut files = changed_files(&repo, &DiffSpec::Base("HEAD~1".to_string()), &all).unwrap();
+        files.sort();
+        assert_eq!(files, ["café.rs", "naïve.rs", "über.rs"].map(|f| root.join(f)));
+        assert_eq!(changed_files(&repo, &DiffSpec::Range("HEAD~1..HEAD".to_string()), &none).unwrap(), [root.join("naïve.rs")]);
+        assert_eq!(changed_files(&repo, &DiffSpec::Staged, &all).unwrap(), [root.join("über.rs")]);
+
+        let mut files = repo.touched_since("1 day ago").unwrap();
+        files.sort();
+        files.dedup();
+        assert_eq!(files, ["café.rs", "naïve.rs", "über.rs"].map(|f| root.join(f)));
+        let commits = repo.last_commits().unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[&root.join("café.rs")].sha, cli(root, &["log", "-1", "--format=%h", "HEAD~1"]).trim());
+        assert_eq!(commits[&root.join("naïve.rs")].author, "Ada");
+        let stats = repo.numstat("HEAD~1", "HEAD").unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].added, stats[0].removed, stats[0].name.as_str()), (Some(1), Some(0), "naïve.rs"));
+    }
+
+    // The kept lines are a longest common subsequence, and the runs turn one side into the other
+    #[test]
+    fn edit_runs_are_shortest_edit_scripts() {
+        let mut seed = 7u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) as usize % 4
+        };
+        for _ in 0..300 {
+            let (a_len, b_len) = (next() * 5, next() * 5);
+            let a: Vec<Vec<u8>> = (0..a_len).map(|_| vec![b'a' + next() as u8, b'\n']).collect();
+            let b: Vec<Vec<u8>> = (0..b_len).map(|_| vec![b'a' + next() as u8, b'\n']).collect();
+            let (a, b): (Vec<&[u8]>, Vec<&[u8]>) = (a.iter().map(Vec::as_slice).collect(), b.iter().map(Vec::as_slice).collect());
+
+            let mut longest = vec![vec![0; b.len() + 1]; a.len() + 1];
+            for i in (0..a.len()).rev() {
+                for j in (0..b.len()).rev() {
+                    longest[i][j] = if a[i] == b[j] { longest[i + 1][j + 1] + 1 } else { longest[i + 1][j].max(longest[i][j + 1]) };
+                }
+            }
+
+            let runs = edit_runs(&a, &b);
+            let removed: usize = runs.iter().map(|r| r.1 - r.0).sum();
+            assert_eq!(a.len() - removed, longest[0][0], "{:?} -> {:?}", a, b);
+            let mut rebuilt: Vec<&[u8]> = Vec::new();
+            let mut at = 0;
+            for &(a_from, a_to, b_from, b_to) in &runs {
+                rebuilt.extend(&a[at..a_from]);
+                rebuilt.extend(&b[b_from..b_to]);
+                at = a_to;
+            }
+            rebuilt.extend(&a[at..]);
+            assert_eq!(rebuilt, b);
+        }
+    }
+}
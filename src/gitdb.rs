@@ -0,0 +1,912 @@
+use anyhow::{bail, Context, Result};
+use flate2::read::ZlibDecoder;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+// GIT REPOSITORY READER
+// Diff mode reads the repository itself instead of running `git`: HEAD, loose and packed
+// refs, loose and packed objects (deltas included), the index and the working tree.
+// Read-only and only as much of the format as diff mode needs: no rename detection, no
+// clean/smudge filters or autocrlf (working copies are hashed as they are on disk), no
+// split or sparse indexes and no SHA-256 repositories.
+
+pub type Oid = [u8; 20];
+
+pub const TREE: u32 = 0o040000;
+pub const SYMLINK: u32 = 0o120000;
+pub const GITLINK: u32 = 0o160000;
+
+// Decoded delta bases kept per pack before the cache starts over
+const BASE_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+// A tree or index entry: what a path points at
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub mode: u32,
+    pub id: Oid,
+}
+
+// One path that differs between two sides; None where the path doesn't exist.
+// Unmerged paths have no new side either: there is no single version of them yet.
+pub struct Change {
+    pub path: String,
+    pub old: Option<Entry>,
+    pub new: Option<Entry>,
+}
+
+pub struct Commit {
+    pub tree: Oid,
+    pub parents: Vec<Oid>,
+    // Committer time, seconds since the epoch: what walks are ordered by
+    pub time: i64,
+    // The first paragraph of the message on one line, like `%s`
+    pub subject: String,
+}
+
+pub struct IndexEntry {
+    // Repo-root-relative, `/`-separated
+    pub path: String,
+    pub entry: Entry,
+    // 0 for merged entries, 1-3 for the sides of a conflict
+    pub stage: u8,
+    // Stat data from `git add`, to skip hashing untouched files
+    mtime: (u32, u32),
+    size: u32,
+    // `git update-index --skip-worktree` / sparse checkout: the working copy doesn't count
+    skip_worktree: bool,
+    // `git add -N`: tracked, but nothing is staged yet
+    intent_to_add: bool,
+}
+
+pub struct Index {
+    pub entries: Vec<IndexEntry>,
+    // When the index was written: files modified since then can't be trusted by stat alone
+    written: Option<std::time::SystemTime>,
+}
+
+struct Pack {
+    file: Mutex<File>,
+    // Sorted object ids and their offsets in the pack, from the .idx
+    ids: Vec<Oid>,
+    offsets: Vec<u64>,
+    bases: Mutex<Bases>,
+}
+
+// Delta bases already decoded, by offset, and their total size
+type Bases = (usize, HashMap<u64, (u8, Arc<Vec<u8>>)>);
+
+pub struct Store {
+    // The working tree root, canonical
+    pub root: PathBuf,
+    // The worktree's own git dir (HEAD, index) and the shared one (objects, refs): the
+    // same directory except in linked worktrees
+    git_dir: PathBuf,
+    common: PathBuf,
+    // The object directory and its alternates
+    objects: Vec<PathBuf>,
+    packs: Vec<Pack>,
+    // Commits of a shallow clone whose parents were never fetched
+    shallow: HashSet<Oid>,
+    packed_refs: OnceLock<HashMap<String, Oid>>,
+}
+
+fn be16(bytes: &[u8], at: usize) -> Result<u16> {
+    let b = bytes.get(at..at + 2).context("Truncated git file")?;
+    Ok(u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn be32(bytes: &[u8], at: usize) -> Result<u32> {
+    let b = bytes.get(at..at + 4).context("Truncated git file")?;
+    Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn be64(bytes: &[u8], at: usize) -> Result<u64> {
+    let b = bytes.get(at..at + 8).context("Truncated git file")?;
+    Ok(u64::from_be_bytes(b.try_into()?))
+}
+
+pub fn parse_hex(text: &str) -> Option<Oid> {
+    let hex = text.get(..40)?;
+    if !text[40..].chars().next().is_none_or(char::is_whitespace) {
+        return None;
+    }
+    let mut id = [0u8; 20];
+    for (i, byte) in id.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(id)
+}
+
+// The id `git hash-object` gives these bytes
+pub fn blob_id(content: &[u8]) -> Oid {
+    let mut object = format!("blob {}\0", content.len()).into_bytes();
+    object.extend_from_slice(content);
+    crate::sha1::digest(&object)
+}
+
+// The offset encoding of pack deltas and v4 index paths: 7 bits a byte, each
+// continuation adding one before the shift so no value has two encodings
+fn offset_varint(bytes: &[u8], at: &mut usize) -> Result<u64> {
+    let mut byte = *bytes.get(*at).context("Truncated git file")?;
+    *at += 1;
+    let mut value = (byte & 0x7f) as u64;
+    while byte & 0x80 != 0 {
+        byte = *bytes.get(*at).context("Truncated git file")?;
+        *at += 1;
+        value = ((value + 1) << 7) | (byte & 0x7f) as u64;
+    }
+    Ok(value)
+}
+
+// Little-endian 7-bit groups: the sizes at the start of a delta
+fn size_varint(bytes: &[u8], at: &mut usize) -> Result<usize> {
+    let (mut value, mut shift) = (0usize, 0);
+    loop {
+        let byte = *bytes.get(*at).context("Corrupt git delta")?;
+        *at += 1;
+        value |= ((byte & 0x7f) as usize).checked_shl(shift).context("Corrupt git delta")?;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+// Copy and insert instructions against the base object
+fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>> {
+    let mut at = 0;
+    if size_varint(delta, &mut at)? != base.len() {
+        bail!("Corrupt git delta (base size mismatch)");
+    }
+    let size = size_varint(delta, &mut at)?;
+    let mut out = Vec::with_capacity(size);
+    while let Some(&op) = delta.get(at) {
+        at += 1;
+        if op & 0x80 != 0 {
+            let (mut offset, mut length) = (0usize, 0usize);
+            for bit in 0..7 {
+                if op & (1 << bit) != 0 {
+                    let byte = *delta.get(at).context("Corrupt git delta")? as usize;
+                    at += 1;
+                    if bit < 4 {
+                        offset |= byte << (8 * bit);
+                    } else {
+                        length |= byte << (8 * (bit - 4));
+                    }
+                }
+            }
+            if length == 0 {
+                length = 0x10000;
+            }
+            out.extend_from_slice(base.get(offset..offset + length).context("Corrupt git delta")?);
+        } else if op != 0 {
+            out.extend_from_slice(delta.get(at..at + op as usize).context("Corrupt git delta")?);
+            at += op as usize;
+        } else {
+            bail!("Corrupt git delta (reserved instruction)");
+        }
+    }
+    if out.len() != size {
+        bail!("Corrupt git delta (result size mismatch)");
+    }
+    Ok(out)
+}
+
+fn kind_name(kind: u8) -> &'static str {
+    match kind {
+        1 => "commit",
+        2 => "tree",
+        3 => "blob",
+        4 => "tag",
+        _ => "unknown",
+    }
+}
+
+impl Pack {
+    // Version 2 .idx: fanout, sorted ids, CRCs, 31-bit offsets, then 64-bit ones
+    fn open(idx: &Path) -> Result<Self> {
+        let bytes = std::fs::read(idx).with_context(|| format!("Failed to read {}", idx.display()))?;
+        if !bytes.starts_with(b"\xfftOc") || be32(&bytes, 4)? != 2 {
+            bail!("Unsupported pack index (only version 2 is read): {}", idx.display());
+        }
+        let count = be32(&bytes, 8 + 255 * 4)? as usize;
+        let ids_at = 8 + 256 * 4;
+        let offsets_at = ids_at + 24 * count;
+        let large_at = offsets_at + 4 * count;
+        let ids = bytes
+            .get(ids_at..ids_at + 20 * count)
+            .context("Truncated pack index")?
+            .chunks_exact(20)
+            .map(|id| id.try_into().unwrap_or_default())
+            .collect();
+        let mut offsets = Vec::with_capacity(count);
+        for i in 0..count {
+            let offset = be32(&bytes, offsets_at + 4 * i)?;
+            offsets.push(if offset & 0x8000_0000 != 0 {
+                be64(&bytes, large_at + 8 * (offset & 0x7fff_ffff) as usize)?
+            } else {
+                offset as u64
+            });
+        }
+        let pack = idx.with_extension("pack");
+        let file = File::open(&pack).with_context(|| format!("Failed to open {}", pack.display()))?;
+        Ok(Pack { file: Mutex::new(file), ids, offsets, bases: Mutex::new((0, HashMap::new())) })
+    }
+
+    fn find(&self, id: &Oid) -> Option<u64> {
+        self.ids.binary_search(id).ok().map(|i| self.offsets[i])
+    }
+}
+
+impl Store {
+    // The repository whose working tree contains `dir`; None outside any repository
+    pub fn discover(dir: &Path) -> Result<Option<Self>> {
+        let start = dir.canonicalize().with_context(|| format!("Failed to resolve {}", dir.display()))?;
+        for root in start.ancestors() {
+            let dot_git = root.join(".git");
+            // Linked worktrees and submodules have a `.git` file pointing at the real git dir
+            let git_dir = if dot_git.is_file() {
+                let text = std::fs::read_to_string(&dot_git).with_context(|| format!("Failed to read {}", dot_git.display()))?;
+                match text.trim().strip_prefix("gitdir:") {
+                    Some(path) => root.join(path.trim()),
+                    None => continue,
+                }
+            } else {
+                dot_git
+            };
+            if git_dir.join("HEAD").is_file() {
+                return Self::open(root, git_dir).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn open(root: &Path, git_dir: PathBuf) -> Result<Self> {
+        let common = match std::fs::read_to_string(git_dir.join("commondir")) {
+            Ok(path) => git_dir.join(path.trim()),
+            Err(_) => git_dir.clone(),
+        };
+        let mut objects = vec![common.join("objects")];
+        if let Ok(alternates) = std::fs::read_to_string(common.join("objects/info/alternates")) {
+            let listed = alternates.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#'));
+            objects.extend(listed.map(|path| common.join("objects").join(path)).collect::<Vec<_>>());
+        }
+
+        let mut packs = Vec::new();
+        for dir in &objects {
+            let Ok(listing) = std::fs::read_dir(dir.join("pack")) else { continue };
+            for entry in listing.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|e| e == "idx") && path.with_extension("pack").is_file() {
+                    packs.push(Pack::open(&path)?);
+                }
+            }
+        }
+
+        let shallow = std::fs::read_to_string(common.join("shallow")).unwrap_or_default();
+        let shallow = shallow.lines().filter_map(parse_hex).collect();
+        Ok(Store { root: root.to_path_buf(), git_dir, common, objects, packs, shallow, packed_refs: OnceLock::new() })
+    }
+
+    // OBJECTS
+
+    pub fn read(&self, id: &Oid) -> Result<(u8, Vec<u8>)> {
+        for pack in &self.packs {
+            if let Some(offset) = pack.find(id) {
+                return self.read_packed(pack, offset);
+            }
+        }
+        let hex = crate::sha1::hex(id);
+        for dir in &self.objects {
+            let path = dir.join(&hex[..2]).join(&hex[2..]);
+            if let Ok(compressed) = std::fs::read(&path) {
+                let mut object = Vec::new();
+                ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut object).with_context(|| format!("Corrupt git object {}", hex))?;
+                // "<kind> <size>\0<content>"
+                let nul = object.iter().position(|&b| b == 0).with_context(|| format!("Corrupt git object {}", hex))?;
+                let kind = match object.split(|&b| b == b' ').next() {
+                    Some(b"commit") => 1,
+                    Some(b"tree") => 2,
+                    Some(b"blob") => 3,
+                    Some(b"tag") => 4,
+                    _ => bail!("Corrupt git object {}", hex),
+                };
+                return Ok((kind, object.split_off(nul + 1)));
+            }
+        }
+        bail!("Git object {} is missing from the repository", hex)
+    }
+
+    // Header "<kind:3 bits><size: 4 bits, then 7 per byte>", then a base reference for deltas
+    // (6: a backwards offset in this pack, 7: an object id), then the zlib stream
+    fn read_packed(&self, pack: &Pack, offset: u64) -> Result<(u8, Vec<u8>)> {
+        enum Base {
+            None,
+            Offset(u64),
+            Id(Oid),
+        }
+        let (kind, base, data) = {
+            let mut file = pack.file.lock().unwrap_or_else(|e| e.into_inner());
+            file.seek(SeekFrom::Start(offset))?;
+            let mut reader = BufReader::new(&mut *file);
+            let mut head = [0u8; 1];
+            reader.read_exact(&mut head)?;
+            let kind = (head[0] >> 4) & 7;
+            let mut size = (head[0] & 15) as usize;
+            let mut shift = 4;
+            while head[0] & 0x80 != 0 {
+                reader.read_exact(&mut head)?;
+                size |= ((head[0] & 0x7f) as usize).checked_shl(shift).context("Corrupt git pack")?;
+                shift += 7;
+            }
+            let base = match kind {
+                6 => {
+                    let mut encoded = Vec::new();
+                    loop {
+                        reader.read_exact(&mut head)?;
+                        encoded.push(head[0]);
+                        if head[0] & 0x80 == 0 {
+                            break;
+                        }
+                    }
+                    let distance = offset_varint(&encoded, &mut 0)?;
+                    Base::Offset(offset.checked_sub(distance).context("Corrupt git pack")?)
+                }
+                7 => {
+                    let mut id = [0u8; 20];
+                    reader.read_exact(&mut id)?;
+                    Base::Id(id)
+                }
+                _ => Base::None,
+            };
+            let mut data = Vec::with_capacity(size);
+            ZlibDecoder::new(reader).take(size as u64).read_to_end(&mut data).context("Corrupt git pack")?;
+            (kind, base, data)
+        };
+
+        let (kind, source) = match base {
+            Base::None if (1..=4).contains(&kind) => return Ok((kind, data)),
+            Base::None => bail!("Corrupt git pack (object type {})", kind),
+            Base::Offset(at) => self.delta_base(pack, at)?,
+            Base::Id(id) => {
+                let (kind, source) = self.read(&id)?;
+                (kind, Arc::new(source))
+            }
+        };
+        Ok((kind, apply_delta(&source, &data)?))
+    }
+
+    // Deltas chain onto shared bases: decode each one once
+    fn delta_base(&self, pack: &Pack, offset: u64) -> Result<(u8, Arc<Vec<u8>>)> {
+        if let Some(hit) = pack.bases.lock().unwrap_or_else(|e| e.into_inner()).1.get(&offset) {
+            return Ok(hit.clone());
+        }
+        let (kind, data) = self.read_packed(pack, offset)?;
+        let data = Arc::new(data);
+        let mut cache = pack.bases.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.0 + data.len() > BASE_CACHE_BYTES {
+            *cache = (0, HashMap::new());
+        }
+        cache.0 += data.len();
+        cache.1.insert(offset, (kind, data.clone()));
+        Ok((kind, data))
+    }
+
+    fn read_kind(&self, id: &Oid, wanted: u8) -> Result<Vec<u8>> {
+        let (kind, data) = self.read(id)?;
+        if kind != wanted {
+            bail!("Git object {} is a {}, not a {}", crate::sha1::hex(id), kind_name(kind), kind_name(wanted));
+        }
+        Ok(data)
+    }
+
+    pub fn blob(&self, id: &Oid) -> Result<Vec<u8>> {
+        self.read_kind(id, 3)
+    }
+
+    pub fn commit(&self, id: &Oid) -> Result<Commit> {
+        let data = self.read_kind(id, 1)?;
+        let text = String::from_utf8_lossy(&data);
+        let (headers, message) = text.split_once("\n\n").unwrap_or((&text, ""));
+        let mut commit = Commit { tree: [0; 20], parents: Vec::new(), time: 0, subject: String::new() };
+        for line in headers.lines() {
+            if let Some(tree) = line.strip_prefix("tree ") {
+                commit.tree = parse_hex(tree).context("Corrupt git commit")?;
+            } else if let Some(parent) = line.strip_prefix("parent ") {
+                commit.parents.push(parse_hex(parent).context("Corrupt git commit")?);
+            } else if let Some(committer) = line.strip_prefix("committer ") {
+                // "Name <email> <seconds> <zone>"
+                commit.time = committer.rsplit(' ').nth(1).and_then(|t| t.parse().ok()).unwrap_or(0);
+            }
+        }
+        if self.shallow.contains(id) {
+            commit.parents.clear();
+        }
+        let paragraph = message.trim_start_matches('\n').split("\n\n").next().unwrap_or_default();
+        commit.subject = paragraph.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        Ok(commit)
+    }
+
+    // "<octal mode> <name>\0<20-byte id>" per entry
+    fn tree(&self, id: &Oid) -> Result<BTreeMap<String, Entry>> {
+        let data = self.read_kind(id, 2)?;
+        let mut entries = BTreeMap::new();
+        let mut rest = data.as_slice();
+        while !rest.is_empty() {
+            let space = rest.iter().position(|&b| b == b' ').context("Corrupt git tree")?;
+            let nul = rest.iter().position(|&b| b == 0).context("Corrupt git tree")?;
+            let mode = u32::from_str_radix(std::str::from_utf8(&rest[..space])?, 8).context("Corrupt git tree")?;
+            let id = rest.get(nul + 1..nul + 21).context("Corrupt git tree")?.try_into()?;
+            entries.insert(String::from_utf8_lossy(&rest[space + 1..nul]).to_string(), Entry { mode, id });
+            rest = &rest[nul + 21..];
+        }
+        Ok(entries)
+    }
+
+    // Every non-tree entry below `tree`, by full path
+    fn files(&self, tree: Option<&Oid>) -> Result<BTreeMap<String, Entry>> {
+        let mut files = BTreeMap::new();
+        let mut pending: Vec<(String, Oid)> = tree.map(|t| (String::new(), *t)).into_iter().collect();
+        while let Some((prefix, id)) = pending.pop() {
+            for (name, entry) in self.tree(&id)? {
+                if entry.mode == TREE {
+                    pending.push((format!("{}{}/", prefix, name), entry.id));
+                } else {
+                    files.insert(format!("{}{}", prefix, name), entry);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    // REFS AND REVISIONS
+
+    fn packed_refs(&self) -> &HashMap<String, Oid> {
+        self.packed_refs.get_or_init(|| {
+            let text = std::fs::read_to_string(self.common.join("packed-refs")).unwrap_or_default();
+            // "<id> <name>"; "^<id>" lines peel the tag above them and "#" lines are traits
+            text.lines()
+                .filter_map(|line| Some((line.get(41..)?.to_string(), parse_hex(line)?)))
+                .collect()
+        })
+    }
+
+    // A ref file in the worktree's or the shared git dir, then packed-refs; symbolic refs
+    // are followed. None for unborn branches and unknown names.
+    fn read_ref(&self, name: &str) -> Option<Oid> {
+        let mut name = name.to_string();
+        for _ in 0..5 {
+            let text = [&self.git_dir, &self.common].iter().find_map(|dir| std::fs::read_to_string(dir.join(&name)).ok());
+            match text {
+                Some(text) => match text.trim().strip_prefix("ref:") {
+                    Some(target) => name = target.trim().to_string(),
+                    None => return parse_hex(text.trim()),
+                },
+                None => return self.packed_refs().get(&name).copied(),
+            }
+        }
+        None
+    }
+
+    // Abbreviated ids: loose objects whose file names start with the rest of the prefix,
+    // and the run of pack ids sorted at the prefix
+    fn find_prefix(&self, hex: &str) -> Result<Option<Oid>> {
+        let mut found = BTreeSet::new();
+        for dir in &self.objects {
+            let Ok(listing) = std::fs::read_dir(dir.join(&hex[..2])) else { continue };
+            for entry in listing.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with(&hex[2..]) {
+                    found.extend(parse_hex(&format!("{}{}", &hex[..2], name)));
+                }
+            }
+        }
+        let lowest = parse_hex(&format!("{:0<40}", hex)).context("Invalid object id")?;
+        for pack in &self.packs {
+            let first = pack.ids.partition_point(|id| *id < lowest);
+            found.extend(pack.ids[first..].iter().take_while(|id| crate::sha1::hex(*id).starts_with(hex)));
+        }
+        if found.len() > 1 {
+            bail!("Ambiguous revision: {} matches {} objects", hex, found.len());
+        }
+        Ok(found.into_iter().next())
+    }
+
+    // The name part of a revision, in `git rev-parse` order: a full id, a pseudo-ref or
+    // full ref name, a tag, branch or remote branch, then an abbreviated id
+    fn lookup(&self, name: &str) -> Result<Option<Oid>> {
+        let name = if name == "@" { "HEAD" } else { name };
+        let hex = name.len() >= 4 && name.len() <= 40 && name.bytes().all(|b| b.is_ascii_hexdigit());
+        if name.len() == 40 && hex {
+            return Ok(parse_hex(&name.to_lowercase()));
+        }
+        if name.is_empty() || name.split('/').any(|part| part.is_empty() || part == "..") {
+            return Ok(None);
+        }
+        let pseudo = name.starts_with("refs/") || name.bytes().all(|b| b.is_ascii_uppercase() || b == b'_');
+        let candidates = [
+            pseudo.then(|| name.to_string()),
+            Some(format!("refs/{}", name)),
+            Some(format!("refs/tags/{}", name)),
+            Some(format!("refs/heads/{}", name)),
+            Some(format!("refs/remotes/{}", name)),
+            Some(format!("refs/remotes/{}/HEAD", name)),
+        ];
+        if let Some(id) = candidates.iter().flatten().find_map(|candidate| self.read_ref(candidate)) {
+            return Ok(Some(id));
+        }
+        if hex {
+            return self.find_prefix(&name.to_lowercase());
+        }
+        Ok(None)
+    }
+
+    // Annotated tags point at their object in an "object <id>" header
+    fn peel(&self, mut id: Oid) -> Result<(u8, Oid)> {
+        for _ in 0..10 {
+            let (kind, data) = self.read(&id)?;
+            if kind != 4 {
+                return Ok((kind, id));
+            }
+            let text = String::from_utf8_lossy(&data);
+            let target = text.lines().find_map(|l| l.strip_prefix("object ")).and_then(parse_hex);
+            id = target.context("Corrupt git tag")?;
+        }
+        bail!("Git tag chain is too deep")
+    }
+
+    // A commit from the forms of `git rev-parse` that name one: a ref or id followed by any
+    // chain of `~N`, `^N` and `^{}` / `^{commit}`
+    pub fn resolve(&self, rev: &str) -> Result<Oid> {
+        let unknown = || anyhow::anyhow!("Unknown revision: {}", rev);
+        let (name, mut suffix) = rev.split_at(rev.find(['~', '^']).unwrap_or(rev.len()));
+        let mut id = self.lookup(name)?.ok_or_else(unknown)?;
+        while !suffix.is_empty() {
+            if let Some(rest) = suffix.strip_prefix("^{") {
+                let end = rest.find('}').ok_or_else(unknown)?;
+                if !matches!(&rest[..end], "" | "commit") {
+                    return Err(unknown());
+                }
+                suffix = &rest[end + 1..];
+                continue;
+            }
+            let op = suffix.as_bytes()[0];
+            let digits = suffix[1..].bytes().take_while(u8::is_ascii_digit).count();
+            let count: usize = if digits == 0 { 1 } else { suffix[1..=digits].parse().map_err(|_| unknown())? };
+            suffix = &suffix[1 + digits..];
+            id = self.commit_id(id, rev)?;
+            if op == b'^' {
+                if count > 0 {
+                    id = *self.commit(&id)?.parents.get(count - 1).ok_or_else(unknown)?;
+                }
+            } else {
+                for _ in 0..count {
+                    id = *self.commit(&id)?.parents.first().ok_or_else(unknown)?;
+                }
+            }
+        }
+        self.commit_id(id, rev)
+    }
+
+    fn commit_id(&self, id: Oid, rev: &str) -> Result<Oid> {
+        match self.peel(id)? {
+            (1, commit) => Ok(commit),
+            (kind, _) => bail!("{} is a {}, not a commit", rev, kind_name(kind)),
+        }
+    }
+
+    // None on an unborn branch
+    pub fn head(&self) -> Option<Oid> {
+        self.read_ref("HEAD")
+    }
+
+    pub fn head_tree(&self) -> Result<Option<Oid>> {
+        self.head().map(|id| Ok(self.commit(&id)?.tree)).transpose()
+    }
+
+    // HISTORY
+
+    // Commits reachable from `include` and not from `exclude`, newest first by committer
+    // date like a plain `git log`. Exclusion spreads down the queue as it is walked and
+    // the walk stops once nothing wanted is left in it.
+    pub fn walk(&self, include: &[Oid], exclude: &[Oid]) -> Result<Vec<(Oid, Commit)>> {
+        let mut commits: HashMap<Oid, Commit> = HashMap::new();
+        let mut hidden: HashMap<Oid, bool> = HashMap::new();
+        let mut queued = HashSet::new();
+        let mut queue = BinaryHeap::new();
+        let mut wanted = 0usize;
+        let mut shown = Vec::new();
+
+        let mut pending: Vec<(Oid, bool)> = exclude.iter().map(|id| (*id, true)).chain(include.iter().map(|id| (*id, false))).collect();
+        loop {
+            for (id, hide) in pending.drain(..) {
+                match hidden.get(&id) {
+                    Some(false) if hide => {
+                        hidden.insert(id, true);
+                        if queued.contains(&id) {
+                            wanted -= 1;
+                        }
+                    }
+                    Some(_) => {}
+                    None => {
+                        let commit = self.commit(&id)?;
+                        queue.push((commit.time, id));
+                        commits.insert(id, commit);
+                        hidden.insert(id, hide);
+                        queued.insert(id);
+                        wanted += usize::from(!hide);
+                    }
+                }
+            }
+            if wanted == 0 {
+                break;
+            }
+            let Some((_, id)) = queue.pop() else { break };
+            queued.remove(&id);
+            let hide = hidden[&id];
+            if !hide {
+                wanted -= 1;
+                shown.push(id);
+            }
+            pending.extend(commits[&id].parents.iter().map(|parent| (*parent, hide)));
+        }
+
+        Ok(shown.into_iter().filter(|id| !hidden[id]).filter_map(|id| Some((id, commits.remove(&id)?))).collect())
+    }
+
+    // The newest commit both sides descend from, walking both histories by date at once
+    pub fn merge_base(&self, a: Oid, b: Oid) -> Result<Option<Oid>> {
+        let mut sides: HashMap<Oid, u8> = HashMap::from([(a, 1)]);
+        *sides.entry(b).or_default() |= 2;
+        let mut queue = BinaryHeap::from([(self.commit(&a)?.time, a), (self.commit(&b)?.time, b)]);
+        while let Some((_, id)) = queue.pop() {
+            let reached = sides[&id];
+            if reached == 3 {
+                return Ok(Some(id));
+            }
+            for parent in self.commit(&id)?.parents {
+                let seen = sides.entry(parent).or_default();
+                if *seen & reached != reached {
+                    *seen |= reached;
+                    queue.push((self.commit(&parent)?.time, parent));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // INDEX AND WORKING TREE
+
+    // "DIRC", version, count, then per entry: stat data, mode, id, flags and the path
+    // (NUL-padded to 8 bytes in v2/v3, prefix-compressed against the previous one in v4).
+    // A repository without an index yet has an empty one.
+    pub fn index(&self) -> Result<Index> {
+        let path = self.git_dir.join("index");
+        let Ok(bytes) = std::fs::read(&path) else {
+            return Ok(Index { entries: Vec::new(), written: None });
+        };
+        if !bytes.starts_with(b"DIRC") {
+            bail!("Corrupt git index: {}", path.display());
+        }
+        let version = be32(&bytes, 4)?;
+        if !(2..=4).contains(&version) {
+            bail!("Unsupported git index version {}", version);
+        }
+        let count = be32(&bytes, 8)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        let mut at = 12;
+        let mut previous: Vec<u8> = Vec::new();
+        for _ in 0..count {
+            let start = at;
+            let flags = be16(&bytes, at + 60)?;
+            let extended = if version >= 3 && flags & 0x4000 != 0 { be16(&bytes, at + 62)? } else { 0 };
+            at += if version >= 3 && flags & 0x4000 != 0 { 64 } else { 62 };
+            let name = if version == 4 {
+                let strip = offset_varint(&bytes, &mut at)? as usize;
+                let end = at + bytes[at..].iter().position(|&b| b == 0).context("Corrupt git index")?;
+                let mut name = previous.get(..previous.len().checked_sub(strip).context("Corrupt git index")?).unwrap_or_default().to_vec();
+                name.extend_from_slice(&bytes[at..end]);
+                at = end + 1;
+                name
+            } else {
+                let end = at + bytes.get(at..).unwrap_or_default().iter().position(|&b| b == 0).context("Corrupt git index")?;
+                let name = bytes[at..end].to_vec();
+                at = start + ((end - start) + 8) / 8 * 8;
+                name
+            };
+            let mode = be32(&bytes, start + 24)?;
+            entries.push(IndexEntry {
+                path: String::from_utf8_lossy(&name).to_string(),
+                entry: Entry { mode, id: bytes.get(start + 40..start + 60).context("Corrupt git index")?.try_into()? },
+                stage: ((flags >> 12) & 3) as u8,
+                mtime: (be32(&bytes, start + 8)?, be32(&bytes, start + 12)?),
+                size: be32(&bytes, start + 36)?,
+                skip_worktree: extended & 0x4000 != 0,
+                intent_to_add: extended & 0x2000 != 0,
+            });
+            previous = name;
+        }
+        // Sparse indexes fold whole directories into one tree entry
+        entries.retain(|e| e.entry.mode != TREE);
+        let written = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        Ok(Index { entries, written })
+    }
+
+    // The working copy of a tracked path: None when it is gone (or no longer a file), the
+    // index's own entry when the stat data says it is untouched, else the hash of its bytes.
+    // Files modified in the same instant the index was written are always hashed.
+    pub fn worktree_entry(&self, index: &Index, tracked: &IndexEntry) -> Result<Option<Entry>> {
+        if tracked.skip_worktree || tracked.entry.mode == GITLINK {
+            return Ok(Some(tracked.entry));
+        }
+        let path = self.root.join(&tracked.path);
+        let Ok(meta) = std::fs::symlink_metadata(&path) else { return Ok(None) };
+        let mode = if meta.file_type().is_symlink() {
+            SYMLINK
+        } else if !meta.is_file() {
+            return Ok(None);
+        } else if executable(&meta, tracked.entry.mode) {
+            0o100755
+        } else {
+            0o100644
+        };
+
+        let modified = meta.modified().ok();
+        let since_epoch = modified.and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok()).unwrap_or_default();
+        let stat_clean = meta.len() as u32 == tracked.size
+            && (since_epoch.as_secs() as u32, since_epoch.subsec_nanos()) == tracked.mtime
+            && matches!((modified, index.written), (Some(file), Some(index)) if file < index);
+        if mode == tracked.entry.mode && stat_clean {
+            return Ok(Some(tracked.entry));
+        }
+        let content = worktree_bytes(&path, mode)?;
+        Ok(Some(Entry { mode, id: blob_id(&content) }))
+    }
+
+    // Tree vs tree, descending only into subtrees that differ
+    pub fn diff_trees(&self, old: Option<&Oid>, new: Option<&Oid>) -> Result<Vec<Change>> {
+        let mut changes = Vec::new();
+        let mut pending = vec![(String::new(), old.copied(), new.copied())];
+        while let Some((prefix, old, new)) = pending.pop() {
+            if old == new {
+                continue;
+            }
+            let old = old.map(|id| self.tree(&id)).transpose()?.unwrap_or_default();
+            let new = new.map(|id| self.tree(&id)).transpose()?.unwrap_or_default();
+            let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for name in names {
+                let path = format!("{}{}", prefix, name);
+                let (before, after) = (old.get(name).copied(), new.get(name).copied());
+                let subtree = |entry: Option<Entry>| entry.filter(|e| e.mode == TREE).map(|e| e.id);
+                let file = |entry: Option<Entry>| entry.filter(|e| e.mode != TREE);
+                if subtree(before).is_some() || subtree(after).is_some() {
+                    pending.push((format!("{}/", path), subtree(before), subtree(after)));
+                }
+                if file(before) != file(after) {
+                    changes.push(Change { path, old: file(before), new: file(after) });
+                }
+            }
+        }
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(changes)
+    }
+
+    // Index vs a tree (`git diff --cached`): what is staged
+    pub fn diff_index(&self, tree: Option<&Oid>, index: &Index) -> Result<Vec<Change>> {
+        let mut base = self.files(tree)?;
+        let mut changes = Vec::new();
+        for tracked in &index.entries {
+            if tracked.stage != 0 {
+                if changes.last().is_none_or(|c: &Change| c.path != tracked.path) {
+                    changes.push(Change { path: tracked.path.clone(), old: base.remove(&tracked.path), new: None });
+                }
+                continue;
+            }
+            let old = base.remove(&tracked.path);
+            if !tracked.intent_to_add && old != Some(tracked.entry) {
+                changes.push(Change { path: tracked.path.clone(), old, new: Some(tracked.entry) });
+            }
+        }
+        changes.extend(base.into_iter().map(|(path, old)| Change { path, old: Some(old), new: None }));
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(changes)
+    }
+
+    // Working tree vs the index (`git diff`): modified but not staged. Untracked files are
+    // not part of it, see `untracked`.
+    pub fn diff_worktree(&self, index: &Index) -> Result<Vec<Change>> {
+        let mut changes: Vec<Change> = Vec::new();
+        for tracked in &index.entries {
+            crate::interrupt::check()?;
+            if tracked.stage != 0 {
+                if changes.last().is_none_or(|c| c.path != tracked.path) {
+                    changes.push(Change { path: tracked.path.clone(), old: None, new: None });
+                }
+                continue;
+            }
+            let current = self.worktree_entry(index, tracked)?;
+            if current != Some(tracked.entry) {
+                changes.push(Change { path: tracked.path.clone(), old: Some(tracked.entry), new: current });
+            }
+        }
+        Ok(changes)
+    }
+
+    // Working tree vs a tree (`git diff <commit>`): tracked paths as they are on disk,
+    // paths only the tree has as deleted
+    pub fn diff_tree_worktree(&self, tree: Option<&Oid>, index: &Index) -> Result<Vec<Change>> {
+        let mut base = self.files(tree)?;
+        let mut changes: Vec<Change> = Vec::new();
+        for tracked in &index.entries {
+            crate::interrupt::check()?;
+            if changes.last().is_some_and(|c| c.path == tracked.path) {
+                continue;
+            }
+            let old = base.remove(&tracked.path);
+            let current = if tracked.stage == 0 { self.worktree_entry(index, tracked)? } else { None };
+            if tracked.stage != 0 || current != old {
+                changes.push(Change { path: tracked.path.clone(), old, new: current });
+            }
+        }
+        changes.extend(base.into_iter().map(|(path, old)| Change { path, old: Some(old), new: None }));
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(changes)
+    }
+
+    // Files on disk the index doesn't know and no ignore rule hides, like
+    // `git status --untracked-files=all`. Nested repositories are left out as a whole.
+    pub fn untracked(&self, index: &Index) -> Result<Vec<String>> {
+        let tracked: HashSet<&str> = index.entries.iter().map(|e| e.path.as_str()).collect();
+        let walker = ignore::WalkBuilder::new(&self.root)
+            .hidden(false)
+            .ignore(false)
+            .parents(false)
+            .require_git(false)
+            .filter_entry(|entry| {
+                let nested = entry.depth() > 0 && entry.file_type().is_some_and(|t| t.is_dir()) && entry.path().join(".git").exists();
+                entry.file_name() != ".git" && !nested
+            })
+            .build();
+        let mut files = Vec::new();
+        for entry in walker.flatten() {
+            crate::interrupt::check()?;
+            if entry.file_type().is_none_or(|t| t.is_dir()) {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else { continue };
+            let name = relative.to_string_lossy().replace('\\', "/");
+            if !tracked.contains(name.as_str()) {
+                files.push(name);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+// Without an executable bit to read, the index's mode stands
+fn executable(meta: &std::fs::Metadata, indexed: u32) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = indexed;
+        meta.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = meta;
+        indexed == 0o100755
+    }
+}
+
+// What git stores for a working copy: the file's bytes, or a symlink's target
+pub fn worktree_bytes(path: &Path, mode: u32) -> Result<Vec<u8>> {
+    if mode == SYMLINK {
+        let target = std::fs::read_link(path).with_context(|| format!("Failed to read link {}", path.display()))?;
+        return Ok(target.to_string_lossy().into_owned().into_bytes());
+    }
+    std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
@@ -0,0 +1,216 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// HTML Report: a self-contained page with a collapsible tree sidebar and one
+// highlighted <section> per file. No external assets, so it can be mailed around.
+//
+// The highlighter is a single regex per language family (comments, strings, numbers,
+// keywords), not a grammar: no syntax-definition crate is in the tree. It colors the
+// common tokens and gets the ambiguous ones right where the family needs it (Rust
+// lifetimes are not char literals), while raw strings, nested comments and
+// interpolation render as plain text.
+
+const STYLE: &str = r#"
+body { margin: 0; display: flex; font-family: -apple-system, "Segoe UI", sans-serif; background: #0f1117; color: #d6d9e0; }
+nav { width: 300px; min-width: 300px; height: 100vh; overflow: auto; position: sticky; top: 0; background: #161922; border-right: 1px solid #262a36; padding: 12px; box-sizing: border-box; font-size: 13px; }
+nav details { margin-left: 10px; }
+nav summary { cursor: pointer; color: #5fd7ff; font-weight: bold; }
+nav a { display: block; margin-left: 14px; color: #d6d9e0; text-decoration: none; white-space: nowrap; }
+nav a:hover { color: #ffd75f; }
+nav .note { color: #ffd75f; font-size: 11px; margin-left: 14px; }
+main { flex: 1; padding: 16px 24px; overflow: auto; }
+section { margin-bottom: 28px; }
+h2 { font-size: 14px; color: #ffd75f; border-bottom: 1px solid #262a36; padding-bottom: 4px; }
+h2 small { color: #7a8194; font-weight: normal; }
+pre { background: #161922; padding: 12px; border-radius: 6px; overflow: auto; font-size: 13px; line-height: 1.45; }
+.kw { color: #c792ea; } .str { color: #c3e88d; } .com { color: #697098; font-style: italic; } .num { color: #f78c6c; }
+"#;
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Stable anchor id for a file section
+fn anchor(relative: &Path) -> String {
+    let raw = relative.display().to_string();
+    let id: String = raw.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+    format!("f-{}", id)
+}
+
+#[derive(Default)]
+struct NavNode {
+    children: BTreeMap<String, NavNode>,
+    file: Option<(PathBuf, Option<String>)>,
+}
+
+impl NavNode {
+    fn render(&self, out: &mut String) {
+        for (name, node) in &self.children {
+            if let Some((relative, note)) = &node.file {
+                out.push_str(&format!("<a href=\"#{}\">{}</a>", anchor(relative), escape(name)));
+                if let Some(note) = note {
+                    out.push_str(&format!("<div class=\"note\">{}</div>", escape(note)));
+                }
+            } else {
+                out.push_str(&format!("<details open><summary>{}</summary>", escape(name)));
+                node.render(out);
+                out.push_str("</details>");
+            }
+        }
+    }
+}
+
+pub fn document_start(root: &str, files: &[PathBuf], notes: &[Option<String>], project_context: Option<&str>) -> String {
+    let root_path = Path::new(root);
+    let mut nav = NavNode::default();
+    for (path, note) in files.iter().zip(notes) {
+        let relative = path.strip_prefix(root_path).unwrap_or(path);
+        let mut current = &mut nav;
+        for component in relative.iter() {
+            current = current.children.entry(component.to_string_lossy().to_string()).or_default();
+        }
+        current.file = Some((relative.to_path_buf(), note.clone()));
+    }
+
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>gimtex :: {}</title>\n<style>{}</style>\n</head>\n<body>\n", escape(root), STYLE));
+    out.push_str(&format!("<nav><details open><summary>{}</summary>", escape(root)));
+    nav.render(&mut out);
+    out.push_str("</details></nav>\n<main>\n");
+    if let Some(context) = project_context {
+        out.push_str(&format!("<section><h2>Project Context</h2><pre>{}</pre></section>\n", escape(context)));
+    }
+    out
+}
+
+pub fn file_section(path: &Path, root: &str, text: &str, label: &str) -> String {
+    let relative = path.strip_prefix(Path::new(root)).unwrap_or(path);
+    let language = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    format!(
+        "<section id=\"{}\"><h2>{} <small>{}</small></h2><pre><code>{}</code></pre></section>\n",
+        anchor(relative),
+        escape(&relative.display().to_string()),
+        escape(label),
+        highlight(text, language)
+    )
+}
+
+pub fn document_end() -> &'static str {
+    "</main>\n</body>\n</html>\n"
+}
+
+// Quoted with single quotes: any string in most languages, one character in Rust, where
+// a lone quote starts a lifetime (`'a`, `'static`)
+const SINGLE_QUOTED: &str = r"'(?:[^'\\\n]|\\.)*'";
+const RUST_CHAR: &str = r"'(?:[^'\\\n]|\\(?:x[0-9a-fA-F]{2}|u\{[0-9a-fA-F]{1,6}\}|.))'";
+
+// Families share comment syntax; keyword lists are deliberately broad rather than exact.
+// Returns the comment pattern, the single-quote pattern and the keywords.
+fn language_rules(extension: &str) -> Option<(&'static str, &'static str, &'static [&'static str])> {
+    const C_LIKE: &[&str] = &[
+        "as", "async", "await", "break", "case", "catch", "class", "const", "continue", "crate", "default",
+        "do", "else", "enum", "export", "extends", "false", "final", "fn", "for", "func", "function", "go",
+        "if", "impl", "import", "in", "interface", "let", "loop", "match", "mod", "mut", "new", "null",
+        "package", "private", "pub", "public", "return", "self", "Self", "static", "struct", "super",
+        "switch", "this", "throw", "trait", "true", "try", "type", "use", "var", "void", "where", "while",
+    ];
+    const HASH: &[&str] = &[
+        "and", "as", "case", "class", "def", "do", "done", "elif", "else", "end", "esac", "except", "fi",
+        "for", "from", "function", "if", "import", "in", "is", "lambda", "module", "None", "not", "or",
+        "pass", "raise", "require", "return", "then", "True", "False", "try", "while", "with", "yield",
+    ];
+    const C_COMMENT: &str = r"//[^\n]*|/\*[\s\S]*?\*/";
+    match extension {
+        "rs" => Some((C_COMMENT, RUST_CHAR, C_LIKE)),
+        "c" | "h" | "cpp" | "hpp" | "cc" | "js" | "jsx" | "ts" | "tsx" | "mjs" | "go" | "java"
+        | "kt" | "swift" | "cs" | "scala" | "php" | "dart" => Some((C_COMMENT, SINGLE_QUOTED, C_LIKE)),
+        "py" | "rb" | "sh" | "bash" | "zsh" | "toml" | "yaml" | "yml" | "pl" | "r" | "ex" | "exs" => Some((r"#[^\n]*", SINGLE_QUOTED, HASH)),
+        "sql" | "lua" | "hs" => Some((r"--[^\n]*", SINGLE_QUOTED, C_LIKE)),
+        _ => None,
+    }
+}
+
+pub fn highlight(text: &str, extension: &str) -> String {
+    let Some((comment, single_quoted, keywords)) = language_rules(extension) else {
+        return escape(text);
+    };
+
+    // Lifetimes are consumed whole and left plain, so `'static` is not a keyword
+    let lifetime = if extension == "rs" { r"|(?P<lt>'[A-Za-z_][A-Za-z0-9_]*)" } else { "" };
+    let pattern = format!(
+        r#"(?P<com>{})|(?P<str>"(?:[^"\\\n]|\\.)*"|{}){}|(?P<num>\b\d[\d_.]*\b)|(?P<kw>\b(?:{})\b)"#,
+        comment,
+        single_quoted,
+        lifetime,
+        keywords.join("|")
+    );
+    let Ok(re) = Regex::new(&pattern) else {
+        return escape(text);
+    };
+
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut last = 0;
+    for caps in re.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        out.push_str(&escape(&text[last..whole.start()]));
+        last = whole.end();
+        if caps.name("lt").is_some() {
+            out.push_str(&escape(whole.as_str()));
+            continue;
+        }
+        let class = ["com", "str", "num", "kw"].into_iter().find(|name| caps.name(name).is_some()).unwrap_or("kw");
+        out.push_str(&format!("<span class=\"{}\">{}</span>", class, escape(whole.as_str())));
+    }
+    out.push_str(&escape(&text[last..]));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rust_lifetimes_are_not_char_literals() {
+        let out = highlight("fn f<'a>(x: &'a str) -> &'static str { \"s\" }", "rs");
+        assert!(!out.contains("<span class=\"str\">&#39;a"), "{}", out);
+        assert!(out.contains("&lt;&#39;a&gt;") && out.contains("&amp;&#39;static"), "{}", out);
+        assert!(out.contains("<span class=\"str\">&quot;s&quot;</span>"), "{}", out);
+        assert!(!out.contains("<span class=\"kw\">static</span>"), "{}", out);
+    }
+
+    #[test]
+    fn rust_char_literals_still_highlight() {
+        for literal in ["'x'", "'\\n'", "'\\''", "'\\u{1F600}'", "'\\x7f'", "'é'"] {
+            let out = highlight(&format!("let c = {};", literal), "rs");
+            assert!(out.contains(&format!("<span class=\"str\">{}</span>", escape(literal))), "{}: {}", literal, out);
+        }
+        // A lifetime next to a char literal keeps both readings apart
+        let out = highlight("impl<'a> X<'a> { const C: char = 'c'; }", "rs");
+        assert_eq!(out.matches("<span class=\"str\">").count(), 1, "{}", out);
+    }
+
+    #[test]
+    fn single_quoted_strings_elsewhere_span_words() {
+        let out = highlight("const s = 'hello world'; // done", "js");
+        assert!(out.contains("<span class=\"str\">&#39;hello world&#39;</span>"), "{}", out);
+        assert!(out.contains("<span class=\"com\">// done</span>"), "{}", out);
+    }
+
+    #[test]
+    fn unknown_languages_are_only_escaped() {
+        assert_eq!(highlight("<b>'x'</b>", "txt"), "&lt;b&gt;&#39;x&#39;&lt;/b&gt;");
+    }
+}
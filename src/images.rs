@@ -0,0 +1,72 @@
+use base64::Engine;
+use std::path::Path;
+
+// IMAGES
+// Screenshots and diagrams referenced by docs, for multimodal models: either a data URI
+// (small files only) or a one-line description standing in for the pixels. SVG is text
+// and goes through the normal pipeline.
+
+// MIME type for the raster formats worth embedding
+pub fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => return None,
+    })
+}
+
+pub fn data_uri(mime: &str, bytes: &[u8]) -> String {
+    format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+// `[image: logo.png, image/png, 640x480, 34.0 KB]`
+pub fn placeholder(path: &Path, mime: &str, bytes: &[u8]) -> String {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let size = crate::output::format_size(bytes.len() as u64);
+    match dimensions(bytes) {
+        Some((width, height)) => format!("[image: {}, {}, {}x{}, {}]", name, mime, width, height, size),
+        None => format!("[image: {}, {}, {}]", name, mime, size),
+    }
+}
+
+// Width and height from the header; PNG, GIF and JPEG cover nearly every screenshot
+fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be32 = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some((be32(16)?, be32(20)?));
+    }
+    if bytes.starts_with(b"GIF8") {
+        let le16 = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32);
+        return Some((le16(6)?, le16(8)?));
+    }
+    if bytes.starts_with(b"\xFF\xD8") {
+        return jpeg_dimensions(bytes);
+    }
+    None
+}
+
+// Walk the marker segments up to the first start-of-frame (SOF0-SOF15, minus DHT/JPG/DAC)
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| bytes.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32);
+    let mut at = 2;
+    while at + 4 <= bytes.len() {
+        if bytes[at] != 0xFF {
+            return None;
+        }
+        let marker = bytes[at + 1];
+        if marker == 0xFF {
+            at += 1;
+            continue;
+        }
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            return Some((be16(at + 7)?, be16(at + 5)?));
+        }
+        at += 2 + be16(at + 2)? as usize;
+    }
+    None
+}
@@ -0,0 +1,184 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+// IMPORT RESOLUTION
+// Regex-level: finds `use`/`mod`, `import`/`from`, `require` and `#include` statements
+// and maps them onto files of the scanned set. Only repo-local dependencies matter here,
+// anything that doesn't resolve to a known file (std, packages) is dropped silently.
+
+// The scanned files, keyed by a lexically normalized form so `./src/a.rs` and `src/a.rs` meet
+pub struct FileIndex<'a> {
+    files: HashMap<PathBuf, &'a PathBuf>,
+}
+
+impl<'a> FileIndex<'a> {
+    pub fn new(files: &'a [PathBuf]) -> Self {
+        Self { files: files.iter().map(|f| (normalize(f), f)).collect() }
+    }
+
+    fn find(&self, candidate: &Path) -> Option<&'a PathBuf> {
+        self.files.get(&normalize(candidate)).copied()
+    }
+
+    fn first(&self, candidates: impl IntoIterator<Item = PathBuf>) -> Option<&'a PathBuf> {
+        candidates.into_iter().find_map(|c| self.find(&c))
+    }
+}
+
+// Drops `.` components and folds `..` without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("static import pattern"))
+}
+
+// Files of the index that `path` imports, in order of first appearance, without itself
+pub fn local_imports<'a>(path: &Path, content: &str, index: &FileIndex<'a>) -> Vec<&'a PathBuf> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let found = match extension {
+        "rs" => rust_imports(path, dir, content, index),
+        "py" => python_imports(dir, content, index),
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => js_imports(dir, content, index),
+        "c" | "h" | "cc" | "cpp" | "hpp" => include_imports(dir, content, index),
+        _ => Vec::new(),
+    };
+
+    let own = normalize(path);
+    let mut unique: Vec<&PathBuf> = Vec::new();
+    for file in found {
+        if normalize(file) != own && !unique.contains(&file) {
+            unique.push(file);
+        }
+    }
+    unique
+}
+
+// Rust: `mod x;` is a sibling module, `use crate::/super::/self::` paths walk the module tree.
+// The module directory of `foo.rs` is `foo/`, of `main.rs`/`lib.rs`/`mod.rs` its own directory.
+fn rust_imports<'a>(path: &Path, dir: &Path, content: &str, index: &FileIndex<'a>) -> Vec<&'a PathBuf> {
+    static MOD: OnceLock<Regex> = OnceLock::new();
+    static USE: OnceLock<Regex> = OnceLock::new();
+    let mod_re = regex(&MOD, r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+([A-Za-z_]\w*)\s*;");
+    let use_re = regex(&USE, r"\b(crate|super|self)::((?:[A-Za-z_]\w*::)*[A-Za-z_]\w*|\{[^}]*\})");
+
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let module_dir = if matches!(stem, "main" | "lib" | "mod") { dir.to_path_buf() } else { dir.join(stem) };
+    let crate_root = path.ancestors().find(|a| a.file_name().is_some_and(|n| n == "src")).unwrap_or(dir);
+
+    let mut found = Vec::new();
+    for caps in mod_re.captures_iter(content) {
+        let name = &caps[1];
+        found.extend(index.first([module_dir.join(format!("{}.rs", name)), module_dir.join(name).join("mod.rs")]));
+    }
+    for caps in use_re.captures_iter(content) {
+        let base = match &caps[1] {
+            "crate" => crate_root.to_path_buf(),
+            "super" => module_dir.parent().unwrap_or(dir).to_path_buf(),
+            _ => module_dir.clone(),
+        };
+        // `crate::{a, b::c}` groups fan out into one path each
+        let tail = caps[2].trim_start_matches('{').trim_end_matches('}');
+        for item in tail.split(',') {
+            let segments: Vec<&str> = item.trim().split("::").filter(|s| !s.is_empty()).collect();
+            // Longest module prefix wins: `crate::a::b::Item` may live in a/b.rs or in a.rs
+            for len in (1..=segments.len()).rev() {
+                let module = segments[..len].iter().fold(base.clone(), |p, s| p.join(s));
+                let hit = index.first([module.with_extension("rs"), module.join("mod.rs")]);
+                if let Some(hit) = hit {
+                    found.push(hit);
+                    break;
+                }
+            }
+        }
+    }
+    found
+}
+
+// Python: relative `from .x import y` resolves next to the file, absolute imports are
+// tried against every ancestor directory (covers both `src/` layouts and flat repos).
+fn python_imports<'a>(dir: &Path, content: &str, index: &FileIndex<'a>) -> Vec<&'a PathBuf> {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    let import_re = regex(&IMPORT, r"(?m)^\s*(?:from\s+(\.*)([\w.]*)\s+import\s+([\w, ]+)|import\s+([\w., ]+))");
+
+    let module_files = |base: &Path, dotted: &str| -> Vec<PathBuf> {
+        let module = dotted.split('.').filter(|s| !s.is_empty()).fold(base.to_path_buf(), |p, s| p.join(s));
+        vec![module.with_extension("py"), module.join("__init__.py")]
+    };
+
+    let mut found = Vec::new();
+    for caps in import_re.captures_iter(content) {
+        if let Some(modules) = caps.get(4) {
+            for module in modules.as_str().split(',') {
+                let module = module.split_whitespace().next().unwrap_or("");
+                found.extend(dir.ancestors().find_map(|base| index.first(module_files(base, module))));
+            }
+            continue;
+        }
+        let dots = caps.get(1).map_or(0, |m| m.as_str().len());
+        let module = caps.get(2).map_or("", |m| m.as_str());
+        let names: Vec<&str> = caps.get(3).map_or("", |m| m.as_str()).split(',').map(|n| n.split_whitespace().next().unwrap_or("")).collect();
+        let bases: Vec<&Path> = if dots > 0 {
+            dir.ancestors().nth(dots - 1).into_iter().collect()
+        } else {
+            dir.ancestors().collect()
+        };
+        for base in bases {
+            // `from pkg import mod` may name submodules rather than attributes
+            let submodules = names.iter().filter_map(|n| index.first(module_files(base, &format!("{}.{}", module, n))));
+            let before = found.len();
+            found.extend(submodules);
+            if !module.is_empty() {
+                found.extend(index.first(module_files(base, module)));
+            }
+            if found.len() > before {
+                break;
+            }
+        }
+    }
+    found
+}
+
+// JS/TS: only relative specifiers point into the repo; packages resolve through node_modules
+fn js_imports<'a>(dir: &Path, content: &str, index: &FileIndex<'a>) -> Vec<&'a PathBuf> {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    let import_re = regex(&IMPORT, r#"(?:\bfrom\s*|\bimport\s*\(?\s*|\brequire\s*\(\s*)["'](\.{1,2}/[^"']+)["']"#);
+
+    const EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+    let mut found = Vec::new();
+    for caps in import_re.captures_iter(content) {
+        let target = dir.join(&caps[1]);
+        let mut candidates = vec![target.clone()];
+        for ext in EXTENSIONS {
+            let mut with_ext = target.clone().into_os_string();
+            with_ext.push(format!(".{}", ext));
+            candidates.push(PathBuf::from(with_ext));
+        }
+        candidates.extend(EXTENSIONS.iter().map(|ext| target.join(format!("index.{}", ext))));
+        found.extend(index.first(candidates));
+    }
+    found
+}
+
+// C/C++: quoted includes are relative to the including file
+fn include_imports<'a>(dir: &Path, content: &str, index: &FileIndex<'a>) -> Vec<&'a PathBuf> {
+    static INCLUDE: OnceLock<Regex> = OnceLock::new();
+    let include_re = regex(&INCLUDE, r#"(?m)^\s*#\s*include\s*"([^"]+)""#);
+    include_re.captures_iter(content).filter_map(|caps| index.find(&dir.join(&caps[1]))).collect()
+}
@@ -0,0 +1,37 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// INTERRUPTS
+// Ctrl+C raises a flag instead of killing the process. Every long stage polls it: a clone
+// or fetch in progress is killed, the walk stops, the processing stage and the secrets and
+// todos audits stop picking up files, the scan bails out at its next check and temp dirs
+// (clones, archives) are dropped on the way out. With --partial, the files processed so far are still written,
+// under a notice that the payload is incomplete. A second Ctrl+C kills as usual.
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    // Only async-signal-safe calls in here: an atomic store and signal(2)
+    unsafe {
+        libc::signal(libc::SIGINT, libc::SIG_DFL);
+    }
+}
+
+pub fn install() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGINT, on_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+}
+
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+pub fn check() -> anyhow::Result<()> {
+    if requested() {
+        anyhow::bail!("Interrupted");
+    }
+    Ok(())
+}
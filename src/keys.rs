@@ -0,0 +1,110 @@
+use anyhow::{Context, Result};
+use console::{Key, Term};
+
+// KEY INPUT
+// The picker and the tui wait for keys without blocking in a read: the terminal is polled,
+// so a resize redraws at once and a SIGINT (Ctrl+C, or `kill -INT` from elsewhere) ends the
+// session instead of waiting for the next key. While a `Keys` is alive the terminal stays
+// out of line mode, or typed keys would sit in the line buffer where polling can't see
+// them; Ctrl+C still raises SIGINT outside a read, and reads take it as `Key::CtrlC`.
+
+// How often the terminal size and the interrupt flag are checked while no key arrives
+const POLL_MS: i32 = 100;
+
+pub enum Input {
+    Key(Key),
+    // The terminal changed size: redraw
+    Resize,
+}
+
+pub struct Keys<'a> {
+    term: &'a Term,
+    #[cfg(unix)]
+    tty: Option<Tty>,
+}
+
+#[cfg(unix)]
+struct Tty {
+    // /dev/tty when stdin is redirected, the same fallback `console` reads from
+    file: Option<std::fs::File>,
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl Tty {
+    fn fd(&self) -> libc::c_int {
+        use std::os::unix::io::AsRawFd;
+        self.file.as_ref().map_or(libc::STDIN_FILENO, |f| f.as_raw_fd())
+    }
+
+    fn open() -> Option<Self> {
+        let file = if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+            None
+        } else {
+            Some(std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?)
+        };
+        let mut tty = Tty { file, original: unsafe { std::mem::zeroed() } };
+        if unsafe { libc::tcgetattr(tty.fd(), &mut tty.original) } != 0 {
+            return None;
+        }
+        let mut unbuffered = tty.original;
+        unbuffered.c_lflag &= !(libc::ICANON | libc::ECHO);
+        unbuffered.c_cc[libc::VMIN] = 1;
+        unbuffered.c_cc[libc::VTIME] = 0;
+        if unsafe { libc::tcsetattr(tty.fd(), libc::TCSADRAIN, &unbuffered) } != 0 {
+            return None;
+        }
+        Some(tty)
+    }
+
+    // A key is waiting, or the timeout passed (or a signal cut the wait short)
+    fn ready(&self, timeout_ms: i32) -> bool {
+        let mut poll = libc::pollfd { fd: self.fd(), events: libc::POLLIN, revents: 0 };
+        unsafe { libc::poll(&mut poll, 1, timeout_ms) > 0 }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Tty {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(self.fd(), libc::TCSADRAIN, &self.original);
+        }
+    }
+}
+
+impl<'a> Keys<'a> {
+    pub fn new(term: &'a Term) -> Self {
+        Self {
+            term,
+            #[cfg(unix)]
+            tty: Tty::open(),
+        }
+    }
+
+    pub fn next(&self) -> Result<Input> {
+        let size = self.term.size();
+        loop {
+            crate::interrupt::check()?;
+            if self.term.size() != size {
+                return Ok(Input::Resize);
+            }
+            #[cfg(unix)]
+            if let Some(tty) = &self.tty {
+                if !tty.ready(POLL_MS) {
+                    continue;
+                }
+            }
+            return match self.term.read_key_raw().context("Failed to read a key")? {
+                Key::CtrlC => anyhow::bail!("Interrupted"),
+                key => Ok(Input::Key(key)),
+            };
+        }
+    }
+
+    // Any key, for "press a key to continue"; resizes don't count
+    pub fn wait(&self) -> Result<()> {
+        while let Input::Resize = self.next()? {}
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+// LOCKFILE SUMMARIES
+// Cargo.lock, package-lock.json, yarn.lock and poetry.lock run to thousands of lines a
+// model gets nothing from. They are replaced by the package count, the direct
+// dependencies with their locked versions (direct = named by the manifest next to the
+// lockfile), and the packages locked in more than one version.
+
+// Longest list printed per section
+const LIST_LIMIT: usize = 50;
+
+pub fn is_lockfile(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    matches!(name.as_str(), "Cargo.lock" | "package-lock.json" | "yarn.lock" | "poetry.lock")
+}
+
+// None when the file doesn't parse; the caller falls back to the verbatim text
+pub fn summary(path: &Path, content: &str) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    let dir = path.parent().unwrap_or(Path::new("."));
+    // (name, version) for every locked package, and the direct dependency names
+    let (packages, direct) = match name.as_str() {
+        "Cargo.lock" => cargo(content)?,
+        "package-lock.json" => npm(content, dir)?,
+        "yarn.lock" => (yarn(content), package_json_dependencies(dir)),
+        "poetry.lock" => (poetry(content)?, pyproject_dependencies(dir)),
+        _ => return None,
+    };
+
+    let mut versions: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for (package, version) in &packages {
+        versions.entry(package.clone()).or_default().insert(version.clone());
+    }
+
+    let mut out = format!("Lockfile summary: {} packages locked ({}; --full-lockfiles for the whole file)\n", packages.len(), name);
+    let direct: Vec<String> = direct
+        .iter()
+        .filter_map(|dep| {
+            let locked = versions.get(dep).or_else(|| versions.get(&normalize_python(dep)))?;
+            Some(format!("{} {}", dep, locked.iter().cloned().collect::<Vec<_>>().join(", ")))
+        })
+        .collect();
+    push_section(&mut out, "Direct dependencies", &direct);
+    let duplicated: Vec<String> = versions
+        .iter()
+        .filter(|(_, v)| v.len() > 1)
+        .map(|(package, v)| format!("{} ({})", package, v.iter().cloned().collect::<Vec<_>>().join(", ")))
+        .collect();
+    push_section(&mut out, "Locked in several versions", &duplicated);
+    Some(out)
+}
+
+fn push_section(out: &mut String, title: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    out.push_str(&format!("\n{} ({}):\n", title, items.len()));
+    for item in items.iter().take(LIST_LIMIT) {
+        out.push_str(&format!("  {}\n", item));
+    }
+    if items.len() > LIST_LIMIT {
+        out.push_str(&format!("  ... and {} more\n", items.len() - LIST_LIMIT));
+    }
+}
+
+type Packages = Vec<(String, String)>;
+
+// Workspace members are the packages without a `source`; their dependencies are direct
+fn cargo(content: &str) -> Option<(Packages, BTreeSet<String>)> {
+    let doc: toml::Value = content.parse().ok()?;
+    let mut packages = Vec::new();
+    let mut direct = BTreeSet::new();
+    let mut members = BTreeSet::new();
+    for package in doc.get("package")?.as_array()? {
+        let name = package.get("name")?.as_str()?.to_string();
+        packages.push((name.clone(), package.get("version")?.as_str()?.to_string()));
+        if package.get("source").is_none() {
+            members.insert(name);
+            let deps = package.get("dependencies").and_then(|d| d.as_array()).into_iter().flatten();
+            // Entries are `name`, `name version` or `name version (source)`
+            direct.extend(deps.filter_map(|d| d.as_str()?.split_whitespace().next().map(str::to_string)));
+        }
+    }
+    packages.retain(|(name, _)| !members.contains(name));
+    direct.retain(|name| !members.contains(name));
+    Some((packages, direct))
+}
+
+fn npm(content: &str, dir: &Path) -> Option<(Packages, BTreeSet<String>)> {
+    let doc: serde_json::Value = serde_json::from_str(content).ok()?;
+    let mut packages = Vec::new();
+    let mut direct = package_json_dependencies(dir);
+    if let Some(entries) = doc["packages"].as_object() {
+        // v2/v3: keyed by install path; the root project is ""
+        for (key, entry) in entries {
+            let Some(name) = key.rsplit_once("node_modules/").map(|(_, n)| n) else { continue };
+            if let Some(version) = entry["version"].as_str() {
+                packages.push((name.to_string(), version.to_string()));
+            }
+        }
+        for field in ["dependencies", "devDependencies", "optionalDependencies"] {
+            if let Some(deps) = entries.get("").and_then(|root| root[field].as_object()) {
+                direct.extend(deps.keys().cloned());
+            }
+        }
+    } else {
+        // v1: a nested `dependencies` tree
+        fn walk(deps: &serde_json::Map<String, serde_json::Value>, out: &mut Packages) {
+            for (name, entry) in deps {
+                if let Some(version) = entry["version"].as_str() {
+                    out.push((name.clone(), version.to_string()));
+                }
+                if let Some(nested) = entry["dependencies"].as_object() {
+                    walk(nested, out);
+                }
+            }
+        }
+        walk(doc["dependencies"].as_object()?, &mut packages);
+    }
+    Some((packages, direct))
+}
+
+// Blocks open with an unindented `"a@^1.0", a@^1.2:` line and carry `version "1.2.3"`
+// (classic) or `version: 1.2.3` (berry)
+fn yarn(content: &str) -> Packages {
+    let mut packages = Vec::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        if !line.starts_with(' ') && line.ends_with(':') && !line.starts_with('#') {
+            let first = line.trim_end_matches(':').split(", ").next().unwrap_or("").trim_matches('"');
+            // The version separator is the last `@`; a leading one belongs to the scope
+            current = first.rfind('@').filter(|&at| at > 0).map(|at| first[..at].to_string());
+            if current.as_deref() == Some("__metadata") {
+                current = None;
+            }
+            continue;
+        }
+        let trimmed = line.trim();
+        if let (Some(name), Some(version)) = (&current, trimmed.strip_prefix("version")) {
+            let version = version.trim_start_matches(':').trim().trim_matches('"');
+            packages.push((name.clone(), version.to_string()));
+            current = None;
+        }
+    }
+    packages
+}
+
+fn poetry(content: &str) -> Option<Packages> {
+    let doc: toml::Value = content.parse().ok()?;
+    let packages = doc.get("package")?.as_array()?;
+    Some(
+        packages
+            .iter()
+            .filter_map(|p| Some((normalize_python(p.get("name")?.as_str()?), p.get("version")?.as_str()?.to_string())))
+            .collect(),
+    )
+}
+
+// PEP 503 names: case and `_`/`.` vs `-` don't matter
+fn normalize_python(name: &str) -> String {
+    name.to_lowercase().replace(['_', '.'], "-")
+}
+
+fn package_json_dependencies(dir: &Path) -> BTreeSet<String> {
+    let Ok(text) = std::fs::read_to_string(dir.join("package.json")) else { return BTreeSet::new() };
+    let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&text) else { return BTreeSet::new() };
+    ["dependencies", "devDependencies", "optionalDependencies", "peerDependencies"]
+        .iter()
+        .filter_map(|field| manifest[field].as_object())
+        .flat_map(|deps| deps.keys().cloned())
+        .collect()
+}
+
+// Poetry tables (`[tool.poetry.dependencies]`, groups) and PEP 621 `project.dependencies`
+fn pyproject_dependencies(dir: &Path) -> BTreeSet<String> {
+    let Ok(text) = std::fs::read_to_string(dir.join("pyproject.toml")) else { return BTreeSet::new() };
+    let Ok(manifest) = text.parse::<toml::Value>() else { return BTreeSet::new() };
+    let mut names = BTreeSet::new();
+    let poetry = manifest.get("tool").and_then(|t| t.get("poetry"));
+    let mut tables: Vec<&toml::Value> = poetry.and_then(|p| p.get("dependencies")).into_iter().collect();
+    tables.extend(poetry.and_then(|p| p.get("dev-dependencies")));
+    if let Some(groups) = poetry.and_then(|p| p.get("group")).and_then(|g| g.as_table()) {
+        tables.extend(groups.values().filter_map(|g| g.get("dependencies")));
+    }
+    for table in tables.iter().filter_map(|t| t.as_table()) {
+        names.extend(table.keys().filter(|k| k.as_str() != "python").map(|k| normalize_python(k)));
+    }
+    let requirements = manifest.get("project").and_then(|p| p.get("dependencies")).and_then(|d| d.as_array());
+    for requirement in requirements.into_iter().flatten().filter_map(|r| r.as_str()) {
+        let name: String = requirement.chars().take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.')).collect();
+        names.insert(normalize_python(&name));
+    }
+    names
+}
@@ -0,0 +1,46 @@
+use colored::*;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+// VERBOSE LOGGING
+// `-v` traces gimtex's own decisions: why each file left the payload, how long each stage
+// took, which config sources were merged. `-vv` adds debug records from the dependencies
+// too, most usefully the walker's `ignoring <path>: <rule>` for every ignore-file match.
+// Records go to stderr next to the status lines; --quiet and -v are mutually exclusive.
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        // -v keeps to gimtex's own records; the crates' info lines are noise at that level
+        metadata.level() <= log::max_level() && (log::max_level() >= LevelFilter::Debug || metadata.target().starts_with("gimtex"))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let tag = match record.level() {
+            Level::Error | Level::Warn | Level::Info => "[v]",
+            Level::Debug | Level::Trace => "[vv]",
+        };
+        // Foreign records name their crate, gimtex's own speak for themselves
+        let origin = if record.target().starts_with("gimtex") { String::new() } else { format!("{}: ", record.target()) };
+        eprintln!("{} {}{}", tag.white().dimmed(), origin, record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+pub fn init(verbosity: u8) {
+    let level = match verbosity {
+        0 => return,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
@@ -1,10 +1,42 @@
+mod archive;
+mod comments;
+mod data;
+mod docs;
+mod encoding;
+mod fnv;
+mod git;
+mod gitdb;
+mod html;
+mod images;
+mod imports;
+mod interrupt;
+mod keys;
+mod lockfiles;
+mod logging;
+mod models;
+mod output;
+mod picker;
+mod remote;
 mod scanner;
+mod selection;
+mod sha1;
+mod sha256;
+mod skeleton;
+mod stats;
+mod structured;
+mod template;
+mod todos;
+mod tui;
+mod xml;
+mod zip;
 
 use anyhow::{Result, Context};
-use clap::{Parser, CommandFactory, FromArgMatches};
+use clap::{Parser, Subcommand, CommandFactory, FromArgMatches};
 use colored::Colorize;
 use std::fs;
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::IsTerminal;
 use std::path::Path;
 
 const BANNER: &str = r#"
@@ -23,147 +55,706 @@ EXAMPLES:
   gimtex -I                       # Interactive: Cherry-pick files
   gimtex -i \"*.rs\"                # Filter: Scan only Rust files
   gimtex src/ -I -o context.md    # Combo: Interactive + Save to file
+  gimtex src/ tests/ README.md    # Multi: Scan the union of several paths
   gimtex https://github.com/user/repo -I  # Remote: Clone & Interactive scan
+  gimtex https://github.com/user/repo/pull/42  # PR: Changed files + description
+  gimtex --diff-ref origin/main   # Branch: Only files this branch changed
+  gimtex --commits v1.2.0..HEAD   # History: Files touched in a commit range
+  gimtex --review origin/main     # Review: Changes in full + imported skeletons
+  gimtex compare v1.0.0 v2.0.0    # Compare: Files differing between two refs
+  gimtex secrets .                # Audit: Secret findings table, non-zero exit for CI
+  gimtex todos src/               # Debt: TODO/FIXME markers with context and age
+  gimtex tui                      # Cockpit: Tree, preview and token dashboard
+  gimtex install-hook             # Guard: Block commits that stage secrets
 ";
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// Path to the directory to scan
+    /// Paths (directories, files or git URLs) to scan
     #[arg()]
-    path: Option<String>,
+    path: Vec<String>,
 
     /// Copy output to clipboard
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     copy: bool,
 
-    /// Output format (markdown, xml)
-    #[arg(short, long, default_value = "markdown")]
-    format: String,
+    /// Print only the payload: no status lines, warnings, security alerts or dashboard (errors still show)
+    #[arg(short, long, global = true)]
+    quiet: bool,
 
-    /// Filter files by glob pattern (e.g. "*.rs")
-    #[arg(short = 'i', long)]
-    filter: Option<String>,
+    /// Log per-file decisions, stage timings and config sources to stderr (-vv adds ignore-rule matches)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
 
-    /// Only extract files changed/staged in git
-    #[arg(short, long)]
-    diff: bool,
+    /// Write run statistics as JSON to FILE: files emitted and skipped by reason, tokens, chars, secrets, time per stage
+    #[arg(long, value_name = "FILE", global = true)]
+    stats_json: Option<String>,
+
+    /// When to use ANSI colors, on stderr and in the markdown payload (NO_COLOR counts as never under auto)
+    #[arg(long, value_name = "WHEN", default_value = "auto", value_parser = ["auto", "always", "never"], global = true)]
+    color: String,
+
+    /// List every skipped file in the closing summary, not just a few per reason
+    #[arg(long, global = true)]
+    show_skips: bool,
+
+    /// Fail, writing nothing, if a file was left out for being too large, unreadable, an unfetched LFS pointer or text-less (filters and binaries don't count)
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Output format (markdown, xml, plain, html). Defaults to plain when stdout is not a TTY
+    #[arg(short, long, value_parser = ["markdown", "xml", "plain", "html"], global = true)]
+    format: Option<String>,
+
+    /// Include only files matching a glob, relative to the scan root (repeatable, e.g. -i "*.rs" -i "*.toml")
+    #[arg(short = 'i', long, value_name = "GLOB", global = true)]
+    filter: Vec<String>,
+
+    /// Exclude files matching a glob, relative to the scan root (repeatable)
+    #[arg(long, value_name = "GLOB", global = true)]
+    exclude: Vec<String>,
+
+    /// Only include files whose contents match this regex
+    #[arg(long, value_name = "REGEX", global = true)]
+    grep: Option<String>,
+
+    /// Limit directory traversal depth (1 = only files directly in the target)
+    #[arg(long, value_name = "N", global = true)]
+    max_depth: Option<usize>,
+
+    /// Include hidden files and directories (e.g. .github/)
+    #[arg(long, global = true)]
+    hidden: bool,
+
+    /// Don't respect .gitignore / .ignore files (gimtex's own ignores still apply)
+    #[arg(long, global = true)]
+    no_ignore: bool,
+
+    /// Recurse into initialized git submodules (also in --diff/--staged) and mark their files in the tree
+    #[arg(long, global = true)]
+    submodules: bool,
+
+    /// Keep generated files: linguist-generated / linguist-vendored in .gitattributes, minified JS/CSS, source maps, codegen (skipped by default)
+    #[arg(long, global = true)]
+    include_generated: bool,
+
+    /// Only include files changed since a duration or date (2w, 36h, 3mo, 2024-05-01); git history, else mtime
+    #[arg(long, value_name = "WHEN", global = true)]
+    since: Option<String>,
+
+    /// Only include files whose last commit is by a matching author (substring of "name <email>")
+    #[arg(long, value_name = "WHO", global = true)]
+    author: Option<String>,
+
+    /// Branch or tag to clone for remote targets
+    #[arg(long, value_name = "NAME", global = true)]
+    branch: Option<String>,
+
+    /// Re-clone remote targets instead of reusing the cache
+    #[arg(long, global = true)]
+    refresh: bool,
+
+    /// Commit to check out for remote targets (fetched after the shallow clone)
+    #[arg(long, value_name = "SHA", conflicts_with = "branch", global = true)]
+    rev: Option<String>,
+
+    /// Add a GitHub issue (URL, or number on the origin repo) with recent comments as ISSUE CONTEXT
+    #[arg(long, value_name = "URL|NUMBER", global = true)]
+    issue: Option<String>,
+
+    /// Only extract files changed in git: vs HEAD by default, or --diff=<rev> / --diff=A..B
+    #[arg(short, long, value_name = "REV|RANGE", num_args = 0..=1, require_equals = true, default_missing_value = "HEAD", global = true)]
+    diff: Option<String>,
+
+    /// Working-copy categories collected by plain --diff (comma-separated)
+    #[arg(long, value_name = "KINDS", value_delimiter = ',', default_value = "staged,unstaged", value_parser = ["staged", "unstaged", "untracked"], global = true)]
+    changes: Vec<String>,
+
+    /// Also include untracked (new, not yet added) files in diff modes
+    #[arg(long, global = true)]
+    untracked: bool,
+
+    /// Emit unified diff hunks instead of full file contents (implies --diff)
+    #[arg(long, global = true)]
+    patch: bool,
+
+    /// Context lines around each hunk in --patch mode
+    #[arg(long, value_name = "N", default_value_t = 3, global = true)]
+    patch_context: usize,
+
+    /// Only extract files staged in the index (pre-commit review context)
+    #[arg(long, conflicts_with_all = ["diff", "diff_ref"], global = true)]
+    staged: bool,
+
+    /// Review context vs a base ref: changed files in full, the local files they import as signature-only skeletons
+    #[arg(long, value_name = "BASE", conflicts_with_all = ["diff", "diff_ref", "staged", "commits"], global = true)]
+    review: Option<String>,
+
+    /// Focus on FILE and the repo-local files it imports, transitively (repeatable)
+    #[arg(long, value_name = "FILE", global = true)]
+    expand_imports: Vec<String>,
+
+    /// Extract the file defining NAME (function, type, class) plus every file that references it
+    #[arg(long, value_name = "NAME", global = true)]
+    symbol: Option<String>,
+
+    /// How many import hops --expand-imports follows from the seed files (default: no limit)
+    #[arg(long, value_name = "N", requires = "expand_imports", global = true)]
+    import_depth: Option<usize>,
+
+    /// Only extract files the current branch changed since forking from REF (e.g. origin/main)
+    #[arg(long, value_name = "REF", conflicts_with = "diff", global = true)]
+    diff_ref: Option<String>,
+
+    /// Extract current contents of every file touched in a commit or range (e.g. v1.2.0..HEAD)
+    #[arg(long, value_name = "REV|RANGE", conflicts_with_all = ["diff", "diff_ref", "staged"], global = true)]
+    commits: Option<String>,
+
+    /// Add each file's on-disk size, line count, mtime and SHA-256 to its header
+    #[arg(long, global = true)]
+    meta: bool,
+
+    /// Add each file's last commit (SHA, author, date) to its header
+    #[arg(long, global = true)]
+    git_meta: bool,
+
+    /// Prefix each line with the short SHA and author that last touched it (git blame)
+    #[arg(long, conflicts_with = "patch", global = true)]
+    blame: bool,
 
     /// Add line numbers to output
-    #[arg(short = 'n', long)]
+    #[arg(short = 'n', long, global = true)]
     numbers: bool,
 
     /// Output to file instead of stdout
-    #[arg(short = 'o', long)]
+    #[arg(short = 'o', long, global = true)]
     output: Option<String>,
 
+    /// On Ctrl+C, still write the files processed so far, marked as a partial payload
+    #[arg(long, global = true)]
+    partial: bool,
+
+    /// Write the selected files to FILE (JSON, paths relative to the scan root) for --selection
+    #[arg(long, value_name = "FILE", global = true)]
+    save_selection: Option<String>,
+
+    /// Replay a saved selection: only the files listed in FILE (with -I, they start marked)
+    #[arg(long, value_name = "FILE", global = true)]
+    selection: Option<String>,
+
+    /// Split the -o output into sequential parts of at most N tokens, never inside a file
+    #[arg(long, value_name = "N", global = true)]
+    chunk_tokens: Option<usize>,
+
+    /// Tokenizer used for all counts (o200k_base for GPT-4o / o1)
+    #[arg(long, default_value = "cl100k_base", value_parser = ["cl100k_base", "o200k_base", "p50k_base"], global = true)]
+    encoding: String,
+
+    /// Fast mode: skip tokenization entirely, report bytes and lines instead
+    #[arg(long, conflicts_with_all = ["max_tokens", "chunk_tokens", "model"], global = true)]
+    no_tokens: bool,
+
+    /// Estimate fit and input cost for a model (gpt-4o, claude-sonnet, ...)
+    #[arg(long, value_name = "NAME", global = true)]
+    model: Option<String>,
+
+    /// Leave out test files (tests/, *_test.go, *.spec.ts, test_*.py...) and inline #[cfg(test)] modules
+    #[arg(long, conflicts_with = "only_tests", global = true)]
+    no_tests: bool,
+
+    /// Only include test files
+    #[arg(long, global = true)]
+    only_tests: bool,
+
+    /// Emit every file in full, even when it comes out identical to another file after processing
+    #[arg(long, global = true)]
+    no_dedup: bool,
+
+    /// Order of the file sections: path (alphabetical), priority (config `priority` globs, README, manifests and entry points first) or imports (dependencies before the files that use them)
+    #[arg(long, default_value = "path", value_parser = ["path", "priority", "imports"], global = true)]
+    order: String,
+
+    /// Process at most N files (priority globs first, then path order), reporting what was cut
+    #[arg(long, value_name = "N", global = true)]
+    max_files: Option<usize>,
+
+    /// Drop files smaller than this many bytes (empty stubs, one-line mod.rs, .gitkeep)
+    #[arg(long, value_name = "BYTES", global = true)]
+    min_size: Option<u64>,
+
     /// Maximum file size in bytes to process (default: 100KB)
-    #[arg(long, default_value_t = 100_000)]
+    #[arg(long, default_value_t = 100_000, global = true)]
     max_size: u64,
 
-    /// Interactive mode: Select files manually
-    #[arg(short = 'I', long)]
+    /// Fetch real content for Git LFS pointer files (listed as "(LFS, size)" otherwise)
+    #[arg(long, global = true)]
+    lfs_fetch: bool,
+
+    /// Emit the contents of .env / .env.local files (listed but withheld by default; .env.example always shows)
+    #[arg(long, global = true)]
+    include_dotenv: bool,
+
+    /// Also mask email addresses, phone numbers and IPv4/IPv6 addresses in the payload
+    #[arg(long, global = true)]
+    scrub_pii: bool,
+
+    /// Don't redact detected secrets (e.g. test fixtures); they are still reported on stderr
+    #[arg(long, global = true)]
+    no_redact: bool,
+
+    /// Write secret findings to FILE: SARIF 2.1.0 when it ends in .sarif, a JSON array otherwise
+    #[arg(long, value_name = "FILE", global = true)]
+    secrets_report: Option<String>,
+
+    /// Append one JSON line per redaction (timestamp, file, rule, line) to FILE
+    #[arg(long, value_name = "FILE", global = true)]
+    redaction_log: Option<String>,
+
+    /// Code map: only declarations (functions, types, methods) per file, bodies and docs dropped
+    #[arg(long, global = true)]
+    map: bool,
+
+    /// Remove line and block comments (per language) before counting tokens
+    #[arg(long, global = true)]
+    strip_comments: bool,
+
+    /// With --strip-comments, keep doc comments (///, /** */, Lua ---, Haskell -- |)
+    #[arg(long, global = true)]
+    keep_doc_comments: bool,
+
+    /// Keep only the first N lines of every file, marking how many were cut
+    #[arg(long, value_name = "N", global = true)]
+    head_lines: Option<usize>,
+
+    /// Keep only the last N lines of every file (with --head-lines: the first and last lines)
+    #[arg(long, value_name = "N", global = true)]
+    tail_lines: Option<usize>,
+
+    /// Replace CSV/TSV/JSON/JSONL files holding more than ROWS rows with an inferred schema and their first ROWS rows
+    #[arg(long, value_name = "ROWS", global = true)]
+    data_preview: Option<usize>,
+
+    /// Include Cargo.lock, package-lock.json, yarn.lock and poetry.lock verbatim instead of summarized
+    #[arg(long, global = true)]
+    full_lockfiles: bool,
+
+    /// Keep CRLF line endings and UTF-8 BOMs instead of normalizing them away
+    #[arg(long, global = true)]
+    no_normalize: bool,
+
+    /// Descend into symlinked directories (loops are detected; links leaving the root are always skipped)
+    #[arg(long, global = true)]
+    follow_symlinks: bool,
+
+    /// Extract plain text from PDF and DOCX files instead of listing them as binary
+    #[arg(long, global = true)]
+    docs: bool,
+
+    /// Images (png, jpg, gif, webp, bmp, ico): skip, describe with a placeholder, or embed as base64 data URIs
+    #[arg(long, default_value = "skip", value_parser = ["skip", "placeholder", "base64"], global = true)]
+    images: String,
+
+    /// Largest image in bytes embedded by --images base64; bigger ones get a placeholder
+    #[arg(long, default_value_t = 200_000, global = true)]
+    image_max_size: u64,
+
+    /// Leave binary files out of the tree entirely (listed as "(binary, size)" by default)
+    #[arg(long, global = true)]
+    skip_binary_tree: bool,
+
+    /// Truncate files larger than --max-size instead of skipping them
+    #[arg(long, global = true)]
+    truncate: bool,
+
+    /// Render the payload through a template file ({{tree}}, {{files}}, {{#each files}}...)
+    #[arg(long, value_name = "FILE", global = true)]
+    template: Option<String>,
+
+    /// Token budget for file contents; files beyond it are omitted and reported
+    #[arg(long, value_name = "N", global = true)]
+    max_tokens: Option<usize>,
+
+    /// How --max-tokens picks files: smallest first, gimtex.toml priority list, or an interactive prompt
+    #[arg(long, default_value = "smallest", value_parser = ["smallest", "priority", "interactive"], global = true)]
+    budget_strategy: String,
+
+    /// Interactive mode: Select files manually (fuzzy filter, Tab to mark, Enter to confirm)
+    #[arg(short = 'I', long, global = true)]
     interactive: bool,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Commands {
+    /// Extract the files that differ between two refs, with a diff-stat summary
+    Compare {
+        /// Older side of the comparison (e.g. v1.0.0)
+        ref_a: String,
+        /// Newer side; its versions of the files are extracted
+        ref_b: String,
+        /// Include both versions, under `<ref_a>/` and `<ref_b>/`
+        #[arg(long)]
+        both: bool,
+    },
+    /// Audit for secrets without emitting content; exits non-zero when anything is found
+    Secrets {
+        /// Paths (directories, files or git URLs) to audit, the current directory by default
+        paths: Vec<String>,
+    },
+    /// Report TODO/FIXME/HACK/XXX markers grouped by file, with surrounding lines and their age from git blame
+    Todos {
+        /// Paths (directories, files or git URLs) to search, the current directory by default
+        paths: Vec<String>,
+    },
+    /// Full-screen curation: file tree, preview and token dashboard; write or copy the payload from there
+    Tui {
+        /// Paths (directories, files or git URLs) to curate, the current directory by default
+        paths: Vec<String>,
+    },
+    /// Install a git pre-commit hook that runs `gimtex secrets --staged` and blocks commits on findings
+    InstallHook {
+        /// Replace an existing pre-commit hook that gimtex didn't write
+        #[arg(long)]
+        force: bool,
+    },
+    /// Manage the remote clone cache (~/.cache/gimtex)
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum CacheAction {
+    /// Delete every cached clone
+    Clean,
+}
+
+impl Args {
+    fn change_set(&self) -> git::ChangeSet {
+        let has = |kind: &str| self.changes.iter().any(|c| c == kind);
+        git::ChangeSet {
+            staged: has("staged"),
+            unstaged: has("unstaged"),
+            untracked: self.untracked || has("untracked"),
+        }
+    }
+
+    fn diff_spec(&self) -> Option<git::DiffSpec> {
+        if self.staged {
+            return Some(git::DiffSpec::Staged);
+        }
+        if let Some(revs) = &self.commits {
+            return Some(git::DiffSpec::History(revs.clone()));
+        }
+        if let Some(base) = self.review.as_ref().or(self.diff_ref.as_ref()) {
+            return Some(git::DiffSpec::Base(base.clone()));
+        }
+        // --patch on its own implies a plain --diff
+        let rev = self.diff.clone().or_else(|| self.patch.then(|| "HEAD".to_string()));
+        rev.map(|rev| {
+            if rev.contains("..") {
+                git::DiffSpec::Range(rev)
+            } else {
+                git::DiffSpec::Commit(rev)
+            }
+        })
+    }
+
+    fn secrets_audit(&self) -> bool {
+        matches!(self.command, Some(Commands::Secrets { .. }))
+    }
+
+    fn todos_report(&self) -> bool {
+        matches!(self.command, Some(Commands::Todos { .. }))
+    }
+
+    fn tui(&self) -> bool {
+        matches!(self.command, Some(Commands::Tui { .. }))
+    }
+
+    fn output_format(&self) -> &str {
+        self.format.as_deref().unwrap_or("markdown")
+    }
+
+    // Whether ANSI escapes may be baked into the payload itself. Only a markdown
+    // preview on an interactive stdout gets colors; files and the clipboard stay clean.
+    // `--color always` bakes them in wherever the markdown goes.
+    fn payload_colors(&self) -> bool {
+        let markdown = self.output_format() == "markdown" && self.template.is_none();
+        match output::color_mode() {
+            output::ColorMode::Always => markdown,
+            output::ColorMode::Never => false,
+            output::ColorMode::Auto => markdown && self.output.is_none() && !self.copy && std::io::stdout().is_terminal(),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct Config {
     ignore: Option<Vec<String>>,
+    /// Globs ranking important files first (used by the priority budget strategy)
+    priority: Option<Vec<String>>,
+    /// Context window / price overrides for --model
+    models: Option<HashMap<String, models::ModelSpec>>,
+    /// Code fence language overrides, keyed by file name or extension
+    languages: Option<HashMap<String, String>>,
+    /// Extra paths (gitignore syntax) whose contents are never emitted, on top of the built-in key/credential list
+    sensitive: Option<Vec<String>>,
+    /// `full` (default) or `partial` redaction markers (first/last 4 characters kept)
+    redaction: Option<scanner::RedactionMode>,
+    /// Extra `[[redact]]` rules applied after the built-in secret detectors
+    redact: Option<Vec<scanner::RedactRule>>,
+    /// Globs (relative to the scan root) whose secrets are reported but not redacted
+    no_redact: Option<Vec<String>>,
+    /// `[rules."<glob>"]` tables: per-pattern max_size, truncate, strip_comments, exclude, include
+    rules: Option<HashMap<String, scanner::FileRule>>,
     // We can add more config fields here later
 }
 
-fn load_config() -> Option<Config> {
-    let config_path = Path::new("gimtex.toml");
-    if config_path.exists() {
-         let content = fs::read_to_string(config_path).ok()?;
-         toml::from_str(&content).ok()
+// Lookup order: scan root first (project-level), then the working directory.
+fn load_config(root: &Path) -> Result<Option<Config>> {
+    for config_path in [root.join("gimtex.toml"), Path::new("gimtex.toml").to_path_buf()] {
+        if config_path.is_file() {
+            let content = fs::read_to_string(&config_path)
+                .with_context(|| format!("Failed to read {}", config_path.display()))?;
+            let cfg = toml::from_str(&content)
+                .with_context(|| format!("Invalid config: {}", config_path.display()))?;
+            crate::status!("{} Config loaded: {}", "[>>]".cyan().bold(), config_path.display());
+            log_config_sections(&cfg, &config_path);
+            return Ok(Some(cfg));
+        }
+        log::debug!("config: no {}", config_path.display());
+    }
+    log::info!("config: no gimtex.toml, built-in defaults only");
+    Ok(None)
+}
+
+fn log_config_sections(cfg: &Config, source: &Path) {
+    let sections = [
+        ("ignore", cfg.ignore.is_some()),
+        ("priority", cfg.priority.is_some()),
+        ("models", cfg.models.is_some()),
+        ("languages", cfg.languages.is_some()),
+        ("sensitive", cfg.sensitive.is_some()),
+        ("redaction", cfg.redaction.is_some()),
+        ("redact", cfg.redact.is_some()),
+        ("no_redact", cfg.no_redact.is_some()),
+        ("rules", cfg.rules.is_some()),
+    ];
+    let set: Vec<&str> = sections.iter().filter(|(_, set)| *set).map(|(name, _)| *name).collect();
+    log::info!("config: {} sets [{}]", source.display(), set.join(", "));
+}
+
+// Which flags came from the command line (as opposed to clap defaults), subcommand included
+fn log_cli_flags(matches: &clap::ArgMatches) {
+    if !log::log_enabled!(log::Level::Info) {
+        return;
+    }
+    let command = Args::command();
+    let mut given = Vec::new();
+    let mut level = Some((&command, matches));
+    while let Some((cmd, current)) = level {
+        // ids() also lists the derive's arg groups; only real arguments count
+        for arg in cmd.get_arguments() {
+            let id = arg.get_id().as_str();
+            if current.value_source(id) == Some(clap::parser::ValueSource::CommandLine) && !given.contains(&id) {
+                given.push(id);
+            }
+        }
+        level = current.subcommand().and_then(|(name, sub)| Some((cmd.find_subcommand(name)?, sub)));
+    }
+    log::info!("config: command line sets [{}]", given.join(", "));
+}
+
+const HOOK_MARKER: &str = "# installed by gimtex install-hook";
+
+// The hook calls this very binary (PATH may differ inside git), shell-quoted
+fn install_hook(force: bool) -> Result<()> {
+    let repo = git::Repo::open(Path::new("."))?;
+    let hooks = repo.hooks_dir().context("Could not locate the git hooks directory")?;
+    let hook = hooks.join("pre-commit");
+    if let Ok(existing) = fs::read_to_string(&hook) {
+        if !existing.contains(HOOK_MARKER) && !force {
+            anyhow::bail!("{} already exists and wasn't written by gimtex (use --force to replace it)", hook.display());
+        }
+    }
+
+    let exe = std::env::current_exe().map(|p| p.display().to_string()).unwrap_or_else(|_| "gimtex".to_string());
+    let script = format!(
+        "#!/bin/sh\n{}\n# Audits the staged files for secrets; bypass once with `git commit --no-verify`.\nexec '{}' secrets --staged -f plain\n",
+        HOOK_MARKER,
+        exe.replace('\'', "'\\''")
+    );
+    fs::create_dir_all(&hooks).with_context(|| format!("Failed to create {}", hooks.display()))?;
+    fs::write(&hook, script).with_context(|| format!("Failed to write {}", hook.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).context("Failed to make the hook executable")?;
+    }
+    crate::status!("{} Pre-commit hook installed: {}", "[OK]".green().bold(), hook.display().to_string().yellow());
+    Ok(())
+}
+
+// `gimtex compare A B`: the differing files are exported from git into a temp dir (B's
+// versions, or both under `A/` and `B/`) and scanned from there, diff-stat table first.
+fn prepare_compare(ref_a: &str, ref_b: &str, both: bool) -> Result<(tempfile::TempDir, Vec<std::path::PathBuf>, String)> {
+    let repo = git::Repo::open(Path::new("."))?;
+    let stats = repo.numstat(ref_a, ref_b)?;
+    let dir = tempfile::Builder::new().prefix("gimtex_compare").tempdir().context("Failed to create temporary bunker")?;
+
+    // Ref names become directory names: `origin/main` -> `origin_main`
+    let label = |rev: &str| rev.replace(['/', '\\', ':'], "_");
+    let sides: Vec<(&str, std::path::PathBuf)> = if both {
+        vec![(ref_a, dir.path().join(label(ref_a))), (ref_b, dir.path().join(label(ref_b)))]
     } else {
-        None
+        vec![(ref_b, dir.path().to_path_buf())]
+    };
+
+    let mut files = Vec::new();
+    let mut table = format!("DIFF STAT ({} -> {}):\n================\n", ref_a, ref_b);
+    let (mut added_total, mut removed_total) = (0, 0);
+    for stat in &stats {
+        for (rev, base) in &sides {
+            let dest = base.join(&stat.name);
+            if repo.export(rev, &stat.name, &dest)? {
+                files.push(dest);
+            }
+        }
+        let count = |n: &Option<usize>| n.map_or("bin".to_string(), |n| n.to_string());
+        table.push_str(&format!("  +{:<6} -{:<6} {}\n", count(&stat.added), count(&stat.removed), stat.name));
+        added_total += stat.added.unwrap_or(0);
+        removed_total += stat.removed.unwrap_or(0);
+    }
+    table.push_str(&format!("  {} files changed, {} insertions(+), {} deletions(-)\n", stats.len(), added_total, removed_total));
+    crate::status!("{} Compare: {} -> {} ({} files)", "[>>]".cyan().bold(), ref_a.yellow(), ref_b.yellow(), stats.len());
+    Ok((dir, files, table))
+}
+
+// clap prints --help and usage errors while parsing, before `--color` has a value, so
+// the choice is read off argv first (last occurrence wins, like clap's own)
+fn early_color_choice() -> String {
+    let argv: Vec<String> = std::env::args().collect();
+    let mut choice = "auto".to_string();
+    for (i, arg) in argv.iter().enumerate() {
+        if let Some(value) = arg.strip_prefix("--color=") {
+            choice = value.to_string();
+        } else if arg == "--color" {
+            if let Some(value) = argv.get(i + 1) {
+                choice = value.clone();
+            }
+        }
     }
+    choice
+}
+
+// Help layout with the banner and examples, colored per --color
+fn help_command() -> clap::Command {
+    let colors = output::stdout_colors();
+    let choice = if colors { clap::ColorChoice::Always } else { clap::ColorChoice::Never };
+    Args::command()
+        .color(choice)
+        .before_help(format!("{}\n{}", output::paint(BANNER.cyan().bold(), colors), output::paint(TAGLINE.white().italic(), colors)))
+        .after_help(output::paint(EXAMPLES.yellow(), colors))
 }
 
 fn main() -> Result<()> {
-    // Inject Cyber-Industrial Aesthetic
-    let banner_colored = format!("{}\n{}", BANNER.cyan().bold(), TAGLINE.white().italic());
-    let examples_colored = EXAMPLES.yellow(); 
+    output::set_color(&early_color_choice());
 
     // Build command manually to inject colored help
-    let command = Args::command()
-        .before_help(banner_colored)
-        .after_help(examples_colored.to_string());
-        
-    let matches = command.get_matches();
+    let matches = help_command().get_matches();
     let mut args = Args::from_arg_matches(&matches)?;
+    output::set_color(&args.color);
+    output::set_quiet(args.quiet);
+    logging::init(args.verbose);
+    log_cli_flags(&matches);
+    interrupt::install();
+
+    // Pipes and redirects get zero-escape output unless a format (or --color always) was asked for explicitly
+    if args.format.is_none() && !std::io::stdout().is_terminal() && output::color_mode() != output::ColorMode::Always {
+        args.format = Some("plain".to_string());
+    }
 
     // Logic hook
-    // Safety: If no path is provided AND --diff is not set AND --interactive is not set, we default to printing help
-    if args.path.is_none() && !args.diff && !args.interactive {
-        let mut cmd = Args::command()
-            .before_help(format!("{}\n{}", BANNER.cyan().bold(), TAGLINE.white().italic()))
-            .after_help(examples_colored.to_string());
-        cmd.print_help()?;
+    // Safety: If no path is provided AND no git diff mode is set AND --interactive is not set, we default to printing help
+    if args.path.is_empty() && args.diff_spec().is_none() && !args.interactive && args.command.is_none() {
+        help_command().print_help()?;
         return Ok(());
     }
 
-    // Config Merge Strategy:
-    // If gimtex.toml exists, we might someday merge ignore patterns etc.
-    // For now, let's just log if we found it to verify the architecture.
-    if let Some(cfg) = load_config() {
-        // In the future, we will pass this config to scanner.
-        // For now, we will just print that we loaded it to confirm Phase 14 success.
-        eprintln!("{} Config loaded: gimtex.toml", "[>>]".cyan().bold());
-        if let Some(ignores) = cfg.ignore {
-             eprintln!("{} Custom Ignores: {:?}", "[>>]".cyan().bold(), ignores);
-             // TODO: Pass these to scanner in a future update or refactor Args to include them
-        }
+    if let Some(Commands::Cache { action: CacheAction::Clean }) = &args.command {
+        return remote::clean_cache();
+    }
+    if let Some(Commands::InstallHook { force }) = &args.command {
+        return install_hook(*force);
     }
 
-    let mut target_path_buf = std::path::PathBuf::from(args.path.as_deref().unwrap_or("."));
-
-    // REMOTE SCOUT PROTOCOL
-    let target_str = args.path.as_deref().unwrap_or(".");
-    let temp_dir; // Keep alive scope
-
-    if target_str.starts_with("http") || target_str.starts_with("git@") {
-        use std::process::Command;
-        use indicatif::{ProgressBar, ProgressStyle};
-
-        let spinner = ProgressBar::new_spinner();
-        spinner.set_style(ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap());
-        spinner.set_message(format!("Locating Remote Target: {}", target_str));
-        spinner.enable_steady_tick(std::time::Duration::from_millis(100));
-
-        // Create Temp Dir
-        temp_dir = tempfile::Builder::new()
-            .prefix("gimtex_remote")
-            .tempdir()
-            .context("Failed to create temporary bunker")?;
-        
-        spinner.set_message("Cloning Data Stream...");
-
-        // Git Clone
-        let status = Command::new("git")
-            .arg("clone")
-            .arg("--depth")
-            .arg("1") // Shallow clone for speed
-            .arg(target_str)
-            .arg(temp_dir.path())
-            .output()
-            .context("Failed to execute git clone")?;
-
-        if !status.status.success() {
-            spinner.finish_with_message(format!("{} Connection Failed", "[X]".red()));
-            eprintln!("{}", String::from_utf8_lossy(&status.stderr));
-            return Ok(());
+    // Target Resolution: remote URLs are cloned first, local paths pass through.
+    let targets = if args.path.is_empty() { vec![".".to_string()] } else { args.path.clone() };
+    let mut temp_dirs = Vec::new(); // Keep alive scope
+    let mut roots = Vec::new();
+    let mut scope = scanner::Scope::default();
+    if let Some(Commands::Compare { ref_a, ref_b, both }) = &args.command {
+        let (dir, files, table) = prepare_compare(ref_a, ref_b, *both)?;
+        scope.only = Some(files);
+        scope.context.push(table);
+        roots.push(dir.path().to_string_lossy().to_string());
+        temp_dirs.push(remote::Workdir::Temp(dir));
+    }
+    let targets = match &args.command {
+        Some(Commands::Secrets { paths } | Commands::Todos { paths } | Commands::Tui { paths }) if !paths.is_empty() => paths.clone(),
+        Some(Commands::Secrets { .. } | Commands::Todos { .. } | Commands::Tui { .. }) => vec![".".to_string()],
+        Some(_) => Vec::new(),
+        None => targets,
+    };
+    for target in &targets {
+        if archive::is_archive(target) {
+            let dir = archive::unpack(target, args.max_size)?;
+            roots.push(dir.path().to_string_lossy().to_string());
+            temp_dirs.push(remote::Workdir::Temp(dir));
+        } else if let Some(pr) = remote::pull_request(target) {
+            let checkout = remote::checkout_pull(&pr)?;
+            let only = scope.only.get_or_insert_with(Vec::new);
+            only.extend(checkout.files.iter().map(|f| checkout.dir.path().join(f)));
+            scope.context.push(checkout.context);
+            roots.push(checkout.dir.path().to_string_lossy().to_string());
+            temp_dirs.push(remote::Workdir::Temp(checkout.dir));
+        } else if remote::is_snippet(target) {
+            let dir = remote::download_snippet(target)?;
+            roots.push(dir.path().to_string_lossy().to_string());
+            temp_dirs.push(remote::Workdir::Temp(dir));
+        } else if remote::is_remote(target) {
+            let mut spec = remote::parse(target);
+            if args.branch.is_some() {
+                spec.branch = args.branch.clone();
+            }
+            if args.rev.is_some() {
+                spec.rev = args.rev.clone();
+            }
+            let temp_dir = remote::clone_cached(&spec, args.refresh)?;
+            let root = match &spec.subdir {
+                Some(subdir) => temp_dir.path().join(subdir),
+                None => temp_dir.path().to_path_buf(),
+            };
+            roots.push(root.to_string_lossy().to_string());
+            temp_dirs.push(temp_dir);
+        } else {
+            roots.push(target.clone());
         }
+    }
 
-        spinner.finish_with_message(format!("{} Target Acquired", "[OK]".green()));
-        target_path_buf = temp_dir.path().to_path_buf();
+    if let Some(ref issue) = args.issue {
+        scope.context.push(remote::issue_context(issue, &scanner::common_root(&roots))?);
     }
 
-    scanner::scan(target_path_buf.to_str().unwrap(), &args)?;
+    // Config Merge Strategy: gimtex.toml supplies project defaults, CLI flags layer on top.
+    let cfg = load_config(&scanner::common_root(&roots))?.unwrap_or_default();
+
+    scanner::scan(&roots, &args, &cfg, &scope)?;
 
     Ok(())
 }
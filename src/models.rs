@@ -0,0 +1,48 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Model Table: context window + list price for input tokens (USD per 1M).
+// Prices drift; gimtex.toml `[models.<name>]` entries override or extend these.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelSpec {
+    pub context: usize,
+    pub input_per_mtok: f64,
+}
+
+const BUILTIN: &[(&str, usize, f64)] = &[
+    ("gpt-4o", 128_000, 2.50),
+    ("gpt-4o-mini", 128_000, 0.15),
+    ("gpt-4.1", 1_047_576, 2.00),
+    ("o1", 200_000, 15.00),
+    ("o3-mini", 200_000, 1.10),
+    ("claude-sonnet", 200_000, 3.00),
+    ("claude-opus", 200_000, 15.00),
+    ("claude-haiku", 200_000, 0.80),
+    ("gemini-1.5-pro", 2_000_000, 1.25),
+    ("gemini-1.5-flash", 1_000_000, 0.075),
+    ("deepseek-chat", 64_000, 0.27),
+];
+
+pub fn resolve(name: &str, overrides: Option<&HashMap<String, ModelSpec>>) -> Option<ModelSpec> {
+    if let Some(spec) = overrides.and_then(|m| m.get(name)) {
+        return Some(spec.clone());
+    }
+    BUILTIN
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|&(_, context, input_per_mtok)| ModelSpec { context, input_per_mtok })
+}
+
+pub fn known_names(overrides: Option<&HashMap<String, ModelSpec>>) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN.iter().map(|(n, _, _)| n.to_string()).collect();
+    if let Some(extra) = overrides {
+        names.extend(extra.keys().filter(|k| !names.contains(k)).cloned().collect::<Vec<_>>());
+    }
+    names
+}
+
+impl ModelSpec {
+    pub fn cost(&self, tokens: usize) -> f64 {
+        tokens as f64 / 1_000_000.0 * self.input_per_mtok
+    }
+}
@@ -0,0 +1,338 @@
+use anyhow::{Result, Context};
+use arboard::Clipboard;
+use colored::*;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use tempfile::NamedTempFile;
+
+// Where the payload ends up. Stdout and file targets are streamed chunk by
+// chunk; only the clipboard needs the whole payload in memory.
+enum Destination {
+    Stdout(BufWriter<io::Stdout>),
+    File { temp: BufWriter<NamedTempFile>, target: PathBuf },
+    Clipboard(Vec<u8>),
+}
+
+// Context-window splitting: the file target becomes `name.partN.ext`, rolled
+// over only between file blocks so a single file never straddles two parts.
+struct Chunking {
+    limit: usize,
+    base: PathBuf,
+    part: usize,
+    part_tokens: usize,
+    written: Vec<(PathBuf, usize)>,
+}
+
+impl Chunking {
+    fn part_path(&self, part: usize) -> PathBuf {
+        let stem = self.base.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "context".to_string());
+        let name = match self.base.extension() {
+            Some(ext) => format!("{}.part{}.{}", stem, part, ext.to_string_lossy()),
+            None => format!("{}.part{}", stem, part),
+        };
+        self.base.with_file_name(name)
+    }
+}
+
+pub struct Payload<'a> {
+    destination: Destination,
+    bpe: Option<&'a tiktoken_rs::CoreBPE>,
+    bytes: usize,
+    tokens: usize,
+    chunking: Option<Chunking>,
+}
+
+fn stage_next_to(target: &Path) -> Result<BufWriter<NamedTempFile>> {
+    // Atomic Write Protocol: stage next to the target so the final rename never crosses filesystems
+    let dir = match target.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let temp = NamedTempFile::new_in(&dir).context("Failed to create temporary output file")?;
+    Ok(BufWriter::new(temp))
+}
+
+fn persist(temp: BufWriter<NamedTempFile>, target: &Path) -> Result<()> {
+    let temp = temp.into_inner().map_err(|e| e.into_error()).context("Failed to flush output file")?;
+    temp.persist(target).context("Failed to move output file into place")?;
+    Ok(())
+}
+
+impl<'a> Payload<'a> {
+    pub fn open(output: Option<&str>, copy: bool, chunk_tokens: Option<usize>, bpe: Option<&'a tiktoken_rs::CoreBPE>) -> Result<Self> {
+        let mut chunking = None;
+        let destination = if let Some(output_path) = output {
+            let mut target = PathBuf::from(output_path);
+            if let Some(limit) = chunk_tokens {
+                let state = Chunking { limit, base: target.clone(), part: 1, part_tokens: 0, written: Vec::new() };
+                target = state.part_path(1);
+                chunking = Some(state);
+            }
+            Destination::File { temp: stage_next_to(&target)?, target }
+        } else if chunk_tokens.is_some() {
+            anyhow::bail!("--chunk-tokens needs -o/--output to name the part files");
+        } else if copy {
+            Destination::Clipboard(Vec::new())
+        } else {
+            Destination::Stdout(BufWriter::new(io::stdout()))
+        };
+
+        Ok(Self { destination, bpe, bytes: 0, tokens: 0, chunking })
+    }
+
+    pub fn push(&mut self, chunk: &str) -> Result<()> {
+        let tokens = self.count(chunk);
+        self.write(chunk, tokens)
+    }
+
+    // A self-contained file block. The body was already tokenized during processing,
+    // so only the framing around it is encoded and the body count is reused as-is.
+    // In chunked mode this is the only place a new part may start: if the block
+    // would overflow the current part, roll over first.
+    pub fn push_file(&mut self, prefix: &str, body: &str, body_tokens: usize, suffix: &str) -> Result<()> {
+        let pieces = [(prefix, self.count(prefix)), (body, body_tokens), (suffix, self.count(suffix))];
+        self.write_unit(&pieces)
+    }
+
+    fn write_unit(&mut self, pieces: &[(&str, usize)]) -> Result<()> {
+        let tokens: usize = pieces.iter().map(|(_, t)| t).sum();
+        let overflow = self.chunking.as_ref().is_some_and(|c| c.part_tokens > 0 && c.part_tokens + tokens > c.limit);
+        if overflow {
+            self.next_part()?;
+        }
+        if let Some(c) = &self.chunking {
+            if tokens > c.limit {
+                crate::status!("{} A single file block ({} tokens) exceeds --chunk-tokens {}", "[!]".yellow().bold(), tokens, c.limit);
+            }
+        }
+        for (piece, piece_tokens) in pieces {
+            self.write(piece, *piece_tokens)?;
+        }
+        Ok(())
+    }
+
+    // --no-tokens runs without a tokenizer; counts simply stay at zero
+    fn count(&self, text: &str) -> usize {
+        self.bpe.map_or(0, |bpe| bpe.encode_with_special_tokens(text).len())
+    }
+
+    fn next_part(&mut self) -> Result<()> {
+        let Some(chunking) = self.chunking.as_mut() else { return Ok(()) };
+        let Destination::File { temp, target } = &mut self.destination else { return Ok(()) };
+
+        let previous = target.clone();
+        let next_target = chunking.part_path(chunking.part + 1);
+        let finished = std::mem::replace(temp, stage_next_to(&next_target)?);
+        persist(finished, &previous)?;
+        chunking.written.push((previous.clone(), chunking.part_tokens));
+        chunking.part += 1;
+        chunking.part_tokens = 0;
+        *target = next_target;
+
+        let header = format!(
+            "CONTINUATION: part {} (continues {})\n==================\n\n",
+            chunking.part,
+            previous.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+        );
+        self.push(&header)
+    }
+
+    fn write(&mut self, chunk: &str, tokens: usize) -> Result<()> {
+        self.tokens += tokens;
+        self.bytes += chunk.len();
+        if let Some(c) = self.chunking.as_mut() {
+            c.part_tokens += tokens;
+        }
+
+        match &mut self.destination {
+            Destination::Stdout(w) => w.write_all(chunk.as_bytes()).context("Failed to write to stdout")?,
+            Destination::File { temp, .. } => temp.write_all(chunk.as_bytes()).context("Failed to write output file")?,
+            Destination::Clipboard(buf) => buf.extend_from_slice(chunk.as_bytes()),
+        }
+        Ok(())
+    }
+
+    pub fn tokens(&self) -> usize {
+        self.tokens
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    // Flush and hand the payload over. Nothing reaches the target path until this succeeds.
+    pub fn finish(self, file_count: usize) -> Result<()> {
+        match self.destination {
+            Destination::Stdout(mut w) => {
+                w.write_all(b"\n")?;
+                w.flush().context("Failed to flush stdout")?;
+            }
+            Destination::File { temp, target } => {
+                persist(temp, &target)?;
+                if let Some(mut chunking) = self.chunking {
+                    chunking.written.push((target, chunking.part_tokens));
+                    crate::status!("{} Output split into {} parts ({} tokens, {}):",
+                        "[OK]".green().bold(),
+                        chunking.written.len(),
+                        self.tokens,
+                        format_size(self.bytes as u64)
+                    );
+                    for (part, tokens) in &chunking.written {
+                        crate::status!("    - {} ({} tokens)", part.display().to_string().yellow(), tokens);
+                    }
+                    return Ok(());
+                }
+                crate::status!("{} Output written to: {} ({} tokens, {})",
+                    "[OK]".green().bold(),
+                    target.display().to_string().yellow(),
+                    self.tokens,
+                    format_size(self.bytes as u64)
+                );
+            }
+            Destination::Clipboard(buf) => {
+                let text = String::from_utf8_lossy(&buf);
+                match Clipboard::new() {
+                    Ok(mut clipboard) => {
+                        if let Err(e) = clipboard.set_text(text.as_ref()) {
+                            eprintln!("{} Clipboard failure: {}", "[X]".red().bold(), e);
+                        } else {
+                            crate::status!("{} Payload generated: {} files, {} chars copied.",
+                                "[OK]".green().bold(),
+                                file_count,
+                                text.len()
+                            );
+                        }
+                    },
+                    Err(e) => eprintln!("{} Clipboard init failure: {}", "[X]".red().bold(), e),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// --quiet: status lines, warnings, alerts and the dashboard are dropped so only the
+// payload remains; `[X]` errors still print through plain `eprintln!`
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+// `eprintln!` unless --quiet
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::output::quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1000.0;
+    let b = bytes as f64;
+    if b < KB {
+        format!("{} B", bytes)
+    } else if b < KB * KB {
+        format!("{:.1} KB", b / KB)
+    } else if b < KB * KB * KB {
+        format!("{:.1} MB", b / (KB * KB))
+    } else {
+        format!("{:.1} GB", b / (KB * KB * KB))
+    }
+}
+
+
+// 12430 -> "12,430"
+pub fn format_count(n: usize) -> String {
+    let digits = n.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// --color: one decision for every escape gimtex writes. `never` (or NO_COLOR, under
+// `auto`) turns them all off and `always` forces them on. `auto` colors stderr when stderr
+// is a terminal, stdout text (help, payload preview) when stdout is one. The stderr side
+// goes through the colored/console overrides; stdout text is rendered by `paint`, which
+// doesn't consult them, so a redirected stderr never strips (or adds) payload escapes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+static COLOR: AtomicU8 = AtomicU8::new(ColorMode::Auto as u8);
+
+pub fn set_color(choice: &str) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    let mode = match choice {
+        "always" => ColorMode::Always,
+        "never" => ColorMode::Never,
+        _ if no_color => ColorMode::Never,
+        _ => ColorMode::Auto,
+    };
+    COLOR.store(mode as u8, Ordering::Relaxed);
+    let stderr = match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => io::stderr().is_terminal(),
+    };
+    colored::control::set_override(stderr);
+    console::set_colors_enabled(stderr);
+    console::set_colors_enabled_stderr(stderr);
+}
+
+pub fn color_mode() -> ColorMode {
+    match COLOR.load(Ordering::Relaxed) {
+        x if x == ColorMode::Always as u8 => ColorMode::Always,
+        x if x == ColorMode::Never as u8 => ColorMode::Never,
+        _ => ColorMode::Auto,
+    }
+}
+
+// Escapes for text headed to stdout (help, the payload preview)
+pub fn stdout_colors() -> bool {
+    match color_mode() {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => io::stdout().is_terminal(),
+    }
+}
+
+// Payload styling: escapes are only baked in when the output format allows them.
+pub fn paint(text: ColoredString, enabled: bool) -> String {
+    if !enabled {
+        return text.input;
+    }
+    const STYLES: [(Styles, &str); 8] = [
+        (Styles::Bold, "1"),
+        (Styles::Dimmed, "2"),
+        (Styles::Italic, "3"),
+        (Styles::Underline, "4"),
+        (Styles::Blink, "5"),
+        (Styles::Reversed, "7"),
+        (Styles::Hidden, "8"),
+        (Styles::Strikethrough, "9"),
+    ];
+    let mut codes: Vec<String> = STYLES.iter().filter(|(style, _)| text.style.contains(*style)).map(|(_, code)| code.to_string()).collect();
+    codes.extend(text.fgcolor.map(|c| c.to_fg_str().into_owned()));
+    codes.extend(text.bgcolor.map(|c| c.to_bg_str().into_owned()));
+    if codes.is_empty() {
+        return text.input;
+    }
+    format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text.input)
+}
@@ -0,0 +1,262 @@
+use anyhow::{Result, Context};
+use colored::*;
+use console::{Key, Term};
+use glob::Pattern;
+use dialoguer::{Confirm, MultiSelect, theme::ColorfulTheme};
+use std::path::{Path, PathBuf};
+use crate::output::{format_count, format_size};
+
+// Interactive Selection: an incremental fuzzy filter over the paths (relative to the scan
+// root). Typing narrows the list, Tab marks files (`marked` holds the initial state),
+// Enter confirms; with nothing marked, Enter takes every file the current query matches.
+// A query starting with `:` is a command instead: `:i GLOB` marks every path the glob
+// matches, `:x GLOB` unmarks them, applied on Enter (the list previews the matches).
+pub fn pick_files(files: &[PathBuf], root: &str, costs: &Costs, budget: Option<usize>, mut marked: Vec<bool>) -> Result<Vec<PathBuf>> {
+    if files.is_empty() {
+        crate::status!("{} Nothing to select: no candidate files found.", "[!]".yellow().bold());
+        return Ok(Vec::new());
+    }
+
+    let term = Term::stderr();
+    if !term.is_term() {
+        anyhow::bail!("Interactive mode requires a terminal (stderr is not a TTY)");
+    }
+
+    let root_path = Path::new(root);
+    let labels: Vec<String> = files
+        .iter()
+        .map(|p| p.strip_prefix(root_path).unwrap_or(p).display().to_string())
+        .collect();
+
+    let keys = crate::keys::Keys::new(&term);
+    let mut query = String::new();
+    let mut matches: Vec<usize> = (0..files.len()).collect();
+    let (mut cursor, mut offset, mut drawn) = (0usize, 0usize, 0usize);
+    let chosen = loop {
+        // Keep the cursor inside the visible window
+        cursor = cursor.min(matches.len().saturating_sub(1));
+        if cursor < offset {
+            offset = cursor;
+        } else if cursor >= offset + PICKER_ROWS {
+            offset = cursor + 1 - PICKER_ROWS;
+        }
+
+        term.clear_last_lines(drawn).context("Failed to redraw the picker")?;
+        let width = term.size().1 as usize;
+        let count = marked.iter().filter(|&&m| m).count();
+        // What Enter would take right now: the marked files, or else every match
+        let total: usize = if count > 0 {
+            (0..files.len()).filter(|&i| marked[i]).map(|i| costs.sizes[i]).sum()
+        } else {
+            matches.iter().map(|&i| costs.sizes[i]).sum()
+        };
+        let mut screen = vec![
+            format!("{} {}   {}", "Filter:".cyan().bold(), query, meter(total, costs.tokens, budget)),
+            format!("{}/{} match, {} marked (Tab mark, Ctrl-A mark all matches, :i/:x GLOB mark/unmark, \u{2191}\u{2193} move, Enter confirm, Esc cancel)", matches.len(), files.len(), count)
+                .white()
+                .dimmed()
+                .to_string(),
+        ];
+        for (row, &i) in matches.iter().enumerate().skip(offset).take(PICKER_ROWS) {
+            let pointer = if row == cursor { ">".cyan().bold() } else { " ".normal() };
+            let check = if marked[i] { "[x]".green() } else { "[ ]".normal() };
+            let cost = costs.describe(costs.sizes[i]);
+            let label = console::truncate_str(&labels[i], width.saturating_sub(10 + cost.len()), "...");
+            let label = if query.starts_with(':') { label.to_string() } else { highlight(&label, &query) };
+            screen.push(format!("{} {} {} {}", pointer, check, label, format!("({})", cost).white().dimmed()));
+        }
+        drawn = screen.len();
+        term.write_line(&screen.join("\n")).context("Failed to draw the picker")?;
+
+        let crate::keys::Input::Key(key) = keys.next()? else { continue };
+        match key {
+            Key::Enter if query.starts_with(':') => {
+                if let Some((mark, _)) = pattern_command(&query) {
+                    for &i in &matches {
+                        marked[i] = mark;
+                    }
+                }
+                query.clear();
+                matches = filter(&labels, &query);
+            }
+            // Esc leaves command mode without applying anything
+            Key::Escape if query.starts_with(':') => {
+                query.clear();
+                matches = filter(&labels, &query);
+            }
+            Key::Enter => {
+                break if count > 0 { (0..files.len()).filter(|&i| marked[i]).collect() } else { matches.clone() };
+            }
+            Key::Escape => break Vec::new(),
+            Key::ArrowUp => cursor = cursor.saturating_sub(1),
+            Key::ArrowDown => cursor += 1,
+            Key::PageUp => cursor = cursor.saturating_sub(PICKER_ROWS),
+            Key::PageDown => cursor += PICKER_ROWS,
+            Key::Tab => {
+                if let Some(&i) = matches.get(cursor) {
+                    marked[i] = !marked[i];
+                    cursor += 1;
+                }
+            }
+            // Ctrl-A: mark every match, or unmark them when they all are already
+            Key::Home => {
+                let all = matches.iter().all(|&i| marked[i]);
+                for &i in &matches {
+                    marked[i] = !all;
+                }
+            }
+            Key::Backspace => {
+                query.pop();
+                matches = filter(&labels, &query);
+            }
+            Key::Char(c) if !c.is_control() => {
+                query.push(c);
+                matches = filter(&labels, &query);
+                cursor = 0;
+            }
+            _ => {}
+        }
+    };
+    term.clear_last_lines(drawn).context("Failed to clear the picker")?;
+
+    crate::status!("{} Selected {} of {} files", "[>>]".cyan().bold(), chosen.len(), files.len());
+
+    Ok(chosen.into_iter().map(|i| files[i].clone()).collect())
+}
+
+// Estimated cost per file, in tokens or (with token counting off) bytes
+pub struct Costs {
+    pub sizes: Vec<usize>,
+    pub tokens: bool,
+}
+
+impl Costs {
+    pub fn describe(&self, size: usize) -> String {
+        if self.tokens { format!("{} tok", format_count(size)) } else { format_size(size as u64) }
+    }
+}
+
+// Running total of the selection, green while it fits --max-tokens, yellow past 90%, red over
+pub fn meter(total: usize, tokens: bool, budget: Option<usize>) -> String {
+    let Some(budget) = budget.filter(|_| tokens) else {
+        let text = if tokens { format!("~{} tok", format_count(total)) } else { format!("~{}", format_size(total as u64)) };
+        return text.bold().to_string();
+    };
+    let text = format!("~{} / {} tok", format_count(total), format_count(budget));
+    let painted = if total > budget {
+        text.red().bold()
+    } else if total * 10 > budget * 9 {
+        text.yellow().bold()
+    } else {
+        text.green().bold()
+    };
+    painted.to_string()
+}
+
+// Asked before the picker opens when this root has a remembered selection
+pub fn confirm_restore(files: usize) -> Result<bool> {
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Restore the previous selection ({} files)?", files))
+        .default(true)
+        .interact_on(&Term::stderr())
+        .context("Failed to ask about the previous selection")
+}
+
+// Rows of the picker list shown at once
+const PICKER_ROWS: usize = 20;
+
+// `:i GLOB` / `:x GLOB`: (mark?, pattern); None while the command is incomplete or invalid
+fn pattern_command(query: &str) -> Option<(bool, Pattern)> {
+    let (verb, glob) = query.strip_prefix(':')?.split_once(' ')?;
+    let mark = match verb {
+        "i" => true,
+        "x" => false,
+        _ => return None,
+    };
+    Some((mark, Pattern::new(glob.trim()).ok()?))
+}
+
+fn filter(labels: &[String], query: &str) -> Vec<usize> {
+    if !query.starts_with(':') {
+        return fuzzy_matches(labels, query);
+    }
+    match pattern_command(query) {
+        Some((_, pattern)) => (0..labels.len()).filter(|&i| pattern.matches(&labels[i])).collect(),
+        None => Vec::new(),
+    }
+}
+
+// Indices of the labels containing the query as a subsequence (case-insensitive), best first
+fn fuzzy_matches(labels: &[String], query: &str) -> Vec<usize> {
+    let mut scored: Vec<(i64, usize)> = labels.iter().enumerate().filter_map(|(i, label)| Some((fuzzy_score(label, query)?, i))).collect();
+    // Stable: equal scores keep path order
+    scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(_, i)| i).collect()
+}
+
+// Consecutive characters and hits at a segment start (`src/|scanner`, `my_|file`) score
+// up, gaps and long paths score down; None when the query isn't a subsequence
+fn fuzzy_score(label: &str, query: &str) -> Option<i64> {
+    let mut score = 0i64;
+    let mut previous: Option<usize> = None;
+    let mut chars = label.char_indices().map(|(at, c)| (at, c.to_ascii_lowercase())).peekable();
+    for wanted in query.chars().map(|c| c.to_ascii_lowercase()) {
+        let (at, _) = chars.by_ref().find(|&(_, c)| c == wanted)?;
+        score += 1;
+        match previous {
+            Some(p) if label[p..at].chars().count() == 1 => score += 5,
+            Some(p) => score -= (label[p..at].chars().count() as i64).min(5),
+            None => {}
+        }
+        let boundary = label[..at].chars().next_back().is_none_or(|b| matches!(b, '/' | '\\' | '_' | '-' | '.'));
+        if boundary {
+            score += 3;
+        }
+        previous = Some(at);
+    }
+    Some(score * 100 - label.len() as i64)
+}
+
+// The first subsequence match of the query drawn in bold
+fn highlight(label: &str, query: &str) -> String {
+    let mut wanted = query.chars().map(|c| c.to_ascii_lowercase()).peekable();
+    let mut out = String::new();
+    for c in label.chars() {
+        if wanted.peek() == Some(&c.to_ascii_lowercase()) {
+            wanted.next();
+            out.push_str(&c.to_string().yellow().bold().to_string());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Budget Negotiation: keep re-prompting until the checked files fit. Returns kept indices.
+pub fn drop_until_fits(items: &[(String, usize)], budget: usize) -> Result<Vec<usize>> {
+    if !Term::stderr().is_term() {
+        anyhow::bail!("The interactive budget strategy requires a terminal (stderr is not a TTY)");
+    }
+
+    let labels: Vec<String> = items.iter().map(|(name, tokens)| format!("{} ({} tok)", name, tokens)).collect();
+    let mut checked = vec![true; items.len()];
+    loop {
+        let total: usize = items.iter().zip(&checked).filter(|(_, &c)| c).map(|((_, t), _)| t).sum();
+        if total <= budget {
+            return Ok((0..items.len()).filter(|&i| checked[i]).collect());
+        }
+
+        let selection = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!("Over budget: {} / {} tokens. Uncheck files to drop", total, budget))
+            .items(&labels)
+            .defaults(&checked)
+            .max_length(20)
+            .interact_on(&Term::stderr())
+            .context("Failed to run interactive selection")?;
+
+        checked = vec![false; items.len()];
+        for i in selection {
+            checked[i] = true;
+        }
+    }
+}
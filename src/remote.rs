@@ -0,0 +1,642 @@
+use anyhow::{Result, Context};
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+// REMOTE SCOUT PROTOCOL
+
+// AUTHENTICATION
+// A token from GIMTEX_GIT_TOKEN (or GITHUB_TOKEN) is handed to git as an HTTP header scoped
+// to the target's host, through GIT_CONFIG_* environment variables: it never lands in argv,
+// the clone's config or any message. curl reads its header from stdin for the same reason.
+
+fn token() -> Option<String> {
+    ["GIMTEX_GIT_TOKEN", "GITHUB_TOKEN"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|t| !t.trim().is_empty()))
+        .map(|t| t.trim().to_string())
+}
+
+// `https://host/...` -> `https://host/`, the scope of `http.<url>.extraHeader`
+fn https_host(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("https://")?;
+    let host = rest.split('/').next().filter(|h| !h.is_empty())?;
+    Some(format!("https://{}/", host))
+}
+
+// A git invocation that never prompts and, for HTTPS remotes, carries the token
+fn git_command(url: &str) -> Command {
+    use base64::Engine;
+
+    let mut command = Command::new("git");
+    command.env("GIT_TERMINAL_PROMPT", "0");
+    if let (Some(token), Some(host)) = (token(), https_host(url)) {
+        // The username half differs per forge; GitHub and Gitea take anything
+        let user = match host.as_str() {
+            "https://bitbucket.org/" => "x-token-auth",
+            "https://gitlab.com/" => "oauth2",
+            _ => "x-access-token",
+        };
+        let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, token));
+        command
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", format!("http.{}.extraHeader", host))
+            .env("GIT_CONFIG_VALUE_0", format!("Authorization: Basic {}", credentials));
+    }
+    command
+}
+
+// The error for a failed fetch: what failed, the tool's stderr, and a hint when the
+// failure is an authentication one
+pub fn fetch_failure(what: &str, stderr: &str) -> anyhow::Error {
+    let lowered = stderr.to_lowercase();
+    let auth = ["authentication failed", "could not read username", "terminal prompts disabled", "401", "403", "404", "permission denied", "repository not found"];
+    let mut message = format!("{}\n{}", what, stderr.trim_end());
+    if auth.iter().any(|needle| lowered.contains(needle)) {
+        let hint = if token().is_some() {
+            "the token in GIMTEX_GIT_TOKEN/GITHUB_TOKEN was rejected or lacks access"
+        } else {
+            "private repository? set GIMTEX_GIT_TOKEN (or GITHUB_TOKEN)"
+        };
+        message.push_str(&format!("\nAuthentication required: {}", hint));
+    }
+    anyhow::anyhow!(message)
+}
+
+// `output()` that gives up on Ctrl+C: git and its transport helpers are killed and the
+// caller's temp dir is dropped on the way out, instead of the interrupt surfacing as a
+// failed clone. They run in their own process group, so the kill reaches the helpers and
+// the terminal's Ctrl+C is left to this check.
+fn interruptible(command: &mut Command, what: &str) -> Result<std::process::Output> {
+    use std::process::Stdio;
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to execute {}", what))?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let status = loop {
+        if crate::interrupt::requested() {
+            #[cfg(unix)]
+            unsafe {
+                libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("Interrupted");
+        }
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    };
+    Ok(std::process::Output { status, stdout: stdout.join().unwrap_or_default(), stderr: stderr.join().unwrap_or_default() })
+}
+
+// Read on a thread so a chatty child never blocks on a full pipe
+fn drain(pipe: Option<impl std::io::Read + Send + 'static>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+pub fn is_remote(target: &str) -> bool {
+    target.starts_with("http") || target.starts_with("git@")
+}
+
+// What a remote target names: the clone URL plus whatever the web URL implied
+pub struct RemoteSpec {
+    pub url: String,
+    // Branch or tag to clone
+    pub branch: Option<String>,
+    // Commit to check out after cloning
+    pub rev: Option<String>,
+    // Subdirectory to sparse-check-out and scan
+    pub subdir: Option<PathBuf>,
+}
+
+impl RemoteSpec {
+    fn plain(url: &str) -> Self {
+        Self { url: url.to_string(), branch: None, rev: None, subdir: None }
+    }
+}
+
+// Browser URLs pointing into a repository, normalized to the clone URL plus ref and subdir.
+// The first segment after the tree marker is taken as the ref (refs with slashes need --branch).
+//   GitHub     https://github.com/org/repo/tree/<ref>/<path>
+//   GitLab     https://<host>/group/sub/repo/-/tree/<ref>/<path>
+//   Bitbucket  https://bitbucket.org/workspace/repo/src/<ref>/<path>
+//   Gitea      https://<host>/org/repo/src/{branch,tag,commit}/<ref>/<path>
+pub fn parse(target: &str) -> RemoteSpec {
+    let Some(rest) = target.strip_prefix("https://").or_else(|| target.strip_prefix("http://")) else {
+        return RemoteSpec::plain(target);
+    };
+    let scheme = &target[..target.len() - rest.len()];
+    let mut segments = rest.trim_end_matches('/').split('/');
+    let host = segments.next().unwrap_or_default();
+    let path: Vec<&str> = segments.collect();
+
+    let position = |marker: &[&str]| path.windows(marker.len()).position(|w| w == marker);
+    let (repo_len, reference, kind_is_commit, tail) = if let Some(i) = position(&["-", "tree"]) {
+        (i, path.get(i + 2), false, i + 3)
+    } else if let Some(i) = ["branch", "tag", "commit"].iter().find_map(|kind| position(&["src", kind])) {
+        (i, path.get(i + 2), path[i + 1] == "commit", i + 3)
+    } else if (host == "github.com" && path.get(2) == Some(&"tree")) || (host == "bitbucket.org" && path.get(2) == Some(&"src")) {
+        (2, path.get(3), false, 4)
+    } else {
+        return RemoteSpec::plain(target);
+    };
+
+    let Some(reference) = reference.filter(|_| repo_len >= 2) else {
+        return RemoteSpec::plain(target);
+    };
+    let repo = path[..repo_len].join("/");
+    let subdir: PathBuf = path.iter().skip(tail).collect();
+    let mut spec = RemoteSpec {
+        url: format!("{}{}/{}.git", scheme, host, repo.trim_end_matches(".git")),
+        branch: Some(reference.to_string()),
+        rev: None,
+        subdir: (!subdir.as_os_str().is_empty()).then_some(subdir),
+    };
+    // Commits can't be cloned by name; they are fetched after a default-branch clone
+    if kind_is_commit {
+        spec.rev = spec.branch.take();
+    }
+    spec
+}
+
+// Shallow-clones the spec into a fresh temp dir. The TempDir must outlive the scan;
+// dropping it wipes the clone. Returns None (after reporting) when git fails.
+// A branch (or tag) goes straight to the clone; `rev` (a commit) is fetched afterwards.
+// Subdirectory specs clone without blobs and sparse-check-out just that tree.
+pub fn clone(spec: &RemoteSpec) -> Result<TempDir> {
+    clone_in(spec, &std::env::temp_dir())
+}
+
+fn clone_in(spec: &RemoteSpec, parent: &Path) -> Result<TempDir> {
+    let url = spec.url.as_str();
+    let spinner = ProgressBar::new_spinner();
+    if crate::output::quiet() {
+        spinner.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+    }
+    spinner.set_style(ProgressStyle::default_spinner()
+        .template("{spinner:.green} {msg}")
+        .unwrap());
+    spinner.set_message(format!("Locating Remote Target: {}", url));
+    spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+
+    // Create Temp Dir
+    let temp_dir = tempfile::Builder::new()
+        .prefix("gimtex_remote")
+        .tempdir_in(parent)
+        .context("Failed to create temporary bunker")?;
+    
+    spinner.set_message("Cloning Data Stream...");
+
+    // Git Clone
+    let mut clone = git_command(url);
+    clone.arg("clone").arg("--depth").arg("1"); // Shallow clone for speed
+    if let Some(branch) = &spec.branch {
+        clone.arg("--branch").arg(branch);
+    }
+    if spec.subdir.is_some() {
+        clone.arg("--filter=blob:none").arg("--sparse");
+    }
+    let status = interruptible(clone.arg(url).arg(temp_dir.path()), "git clone")?;
+
+    if !status.status.success() {
+        spinner.finish_with_message(format!("{} Connection Failed", "[X]".red()));
+        return Err(fetch_failure(&format!("Could not clone {}", url), &String::from_utf8_lossy(&status.stderr)));
+    }
+
+    if let Some(rev) = &spec.rev {
+        spinner.set_message(format!("Fetching Revision {}...", rev));
+        if let Err(e) = fetch_checkout(temp_dir.path(), url, rev) {
+            spinner.finish_with_message(format!("{} Revision Unavailable", "[X]".red()));
+            return Err(e);
+        }
+    }
+
+    if let Some(subdir) = &spec.subdir {
+        spinner.set_message(format!("Sparse Checkout: {}", subdir.display()));
+        let output = interruptible(git_command(url).arg("-C").arg(temp_dir.path()).args(["sparse-checkout", "set", "--"]).arg(subdir), "git sparse-checkout")?;
+        if !output.status.success() || !temp_dir.path().join(subdir).is_dir() {
+            spinner.finish_with_message(format!("{} Subdirectory Not Found: {}", "[X]".red(), subdir.display()));
+            return Err(fetch_failure(&format!("No directory {} in {}", subdir.display(), url), &String::from_utf8_lossy(&output.stderr)));
+        }
+    }
+
+    spinner.finish_with_message(format!("{} Target Acquired", "[OK]".green()));
+    Ok(temp_dir)
+}
+
+// CLONE CACHE
+// Clones persist under ~/.cache/gimtex/<hash of url+ref+subdir>, so repeated scans of the
+// same target skip the network. `--refresh` re-clones; `gimtex cache clean` wipes it all.
+
+// A checkout to scan: a throwaway temp dir (dropping it wipes the clone) or a cache entry
+pub enum Workdir {
+    Temp(TempDir),
+    Cached(PathBuf),
+}
+
+impl Workdir {
+    pub fn path(&self) -> &Path {
+        match self {
+            Workdir::Temp(dir) => dir.path(),
+            Workdir::Cached(dir) => dir,
+        }
+    }
+}
+
+fn cache_root() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("gimtex"))
+}
+
+// FNV-1a, so a clone lands in the same cache directory on every build
+fn cache_key(spec: &RemoteSpec) -> String {
+    let key = format!(
+        "{}#{}@{}:{}",
+        spec.url,
+        spec.branch.as_deref().unwrap_or(""),
+        spec.rev.as_deref().unwrap_or(""),
+        spec.subdir.as_ref().map(|d| d.display().to_string()).unwrap_or_default()
+    );
+    format!("{:016x}", crate::fnv::fnv1a(key.as_bytes()))
+}
+
+pub fn clone_cached(spec: &RemoteSpec, refresh: bool) -> Result<Workdir> {
+    let Some(root) = cache_root() else {
+        return Ok(Workdir::Temp(clone(spec)?));
+    };
+    let entry = root.join(cache_key(spec));
+    if entry.is_dir() && !refresh {
+        crate::status!("{} Using cached clone of {} (--refresh to re-fetch)", "[>>]".cyan().bold(), spec.url);
+        return Ok(Workdir::Cached(entry));
+    }
+
+    // Clone next to the entry, then swap it in, so an interrupted clone never looks cached
+    std::fs::create_dir_all(&root).with_context(|| format!("Failed to create cache dir {}", root.display()))?;
+    let staged = clone_in(spec, &root)?;
+    if entry.exists() {
+        std::fs::remove_dir_all(&entry).with_context(|| format!("Failed to replace cache entry {}", entry.display()))?;
+    }
+    let staged = staged.keep();
+    std::fs::rename(&staged, &entry).with_context(|| format!("Failed to store cache entry {}", entry.display()))?;
+    Ok(Workdir::Cached(entry))
+}
+
+pub fn clean_cache() -> Result<()> {
+    let Some(root) = cache_root().filter(|r| r.is_dir()) else {
+        crate::status!("{} Cache is empty", "[OK]".green().bold());
+        return Ok(());
+    };
+    let entries = std::fs::read_dir(&root)?.filter_map(|e| e.ok()).count();
+    let size: u64 = dir_size(&root);
+    std::fs::remove_dir_all(&root).with_context(|| format!("Failed to remove {}", root.display()))?;
+    crate::status!("{} Removed {} cached clones ({}) from {}",
+        "[OK]".green().bold(),
+        entries,
+        crate::output::format_size(size),
+        root.display()
+    );
+    Ok(())
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| match e.file_type() {
+                    Ok(t) if t.is_dir() => dir_size(&e.path()),
+                    _ => e.metadata().map(|m| m.len()).unwrap_or(0),
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+// Shallow-fetches `refspec` (a commit or a server-side ref) and detaches the checkout onto it
+fn fetch_checkout(dir: &Path, url: &str, refspec: &str) -> Result<()> {
+    for args in [vec!["fetch", "--depth", "1", "origin", refspec], vec!["checkout", "-q", "--detach", "FETCH_HEAD"]] {
+        let output = interruptible(git_command(url).arg("-C").arg(dir).args(&args), "git")?;
+        if !output.status.success() {
+            return Err(fetch_failure(&format!("Could not check out {}", refspec), &String::from_utf8_lossy(&output.stderr)));
+        }
+    }
+    Ok(())
+}
+
+// PULL REQUEST TARGETS
+// `https://github.com/org/repo/pull/123`: the PR head is fetched onto a shallow clone and
+// the scan is restricted to the files the PR touches, with its description as context.
+
+pub struct PullRequest {
+    owner: String,
+    repo: String,
+    number: u64,
+}
+
+pub fn pull_request(target: &str) -> Option<PullRequest> {
+    let rest = target.strip_prefix("https://github.com/").or_else(|| target.strip_prefix("http://github.com/"))?;
+    let mut parts = rest.trim_end_matches('/').split('/');
+    let (owner, repo, kind, number) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if kind != "pull" {
+        return None;
+    }
+    Some(PullRequest { owner: owner.to_string(), repo: repo.to_string(), number: number.parse().ok()? })
+}
+
+pub struct PullCheckout {
+    pub dir: TempDir,
+    // Changed files (still present at the PR head), relative to the checkout
+    pub files: Vec<PathBuf>,
+    pub context: String,
+}
+
+pub fn checkout_pull(pr: &PullRequest) -> Result<PullCheckout> {
+    let api = format!("repos/{}/{}/pulls/{}", pr.owner, pr.repo, pr.number);
+    let meta = github_api(&api)?;
+    let title = meta["title"].as_str().unwrap_or("(untitled)");
+    let head = meta["head"]["ref"].as_str().unwrap_or("?");
+    let base = meta["base"]["ref"].as_str().unwrap_or("?");
+    crate::status!("{} Pull Request #{}: {} ({} -> {})", "[>>]".cyan().bold(), pr.number, title.yellow(), head, base);
+
+    // The files endpoint pages at 100 entries; removed files have nothing to extract
+    let mut files = Vec::new();
+    for page in 1.. {
+        let listed = github_api(&format!("{}/files?per_page=100&page={}", api, page))?;
+        let entries = listed.as_array().map(|a| a.as_slice()).unwrap_or_default();
+        files.extend(entries
+            .iter()
+            .filter(|f| f["status"].as_str() != Some("removed"))
+            .filter_map(|f| f["filename"].as_str())
+            .map(PathBuf::from));
+        if entries.len() < 100 {
+            break;
+        }
+    }
+
+    let url = format!("https://github.com/{}/{}.git", pr.owner, pr.repo);
+    let dir = clone(&RemoteSpec::plain(&url))?;
+    fetch_checkout(dir.path(), &url, &format!("pull/{}/head", pr.number))?;
+
+    let context = format!(
+        "PULL REQUEST #{}: {}\n================\n{} -> {} ({} files)\n\n{}\n",
+        pr.number,
+        title,
+        head,
+        base,
+        files.len(),
+        meta["body"].as_str().unwrap_or("").trim()
+    );
+    Ok(PullCheckout { dir, files, context })
+}
+
+// ISSUE CONTEXT
+// `--issue` takes an issue/PR URL, or a bare number resolved against the scan root's
+// `origin` remote. Title, body and the most recent comments become a context section.
+
+const RECENT_COMMENTS: usize = 5;
+
+pub fn issue_context(reference: &str, root: &Path) -> Result<String> {
+    let (owner, repo, number) = match issue_reference(reference) {
+        Some(parsed) => parsed,
+        None => {
+            let number: u64 = reference.trim_start_matches('#').parse()
+                .with_context(|| format!("--issue expects a GitHub issue URL or number, got `{}`", reference))?;
+            let origin = crate::git::remote_url(root).context("--issue with a bare number needs a git `origin` remote")?;
+            let (owner, repo) = github_slug(&origin).with_context(|| format!("origin is not a GitHub repository: {}", origin))?;
+            (owner, repo, number)
+        }
+    };
+
+    let api = format!("repos/{}/{}/issues/{}", owner, repo, number);
+    let issue = github_api(&api)?;
+    let title = issue["title"].as_str().unwrap_or("(untitled)");
+    crate::status!("{} Issue #{}: {}", "[>>]".cyan().bold(), number, title.yellow());
+
+    let mut section = format!(
+        "ISSUE CONTEXT (#{}): {}\n================\n{} by {}\n\n{}\n",
+        number,
+        title,
+        issue["state"].as_str().unwrap_or("unknown"),
+        issue["user"]["login"].as_str().unwrap_or("unknown"),
+        issue["body"].as_str().unwrap_or("").trim()
+    );
+
+    let total = issue["comments"].as_u64().unwrap_or(0) as usize;
+    if total > 0 {
+        let mut comments = Vec::new();
+        for page in recent_pages(total) {
+            let listed = github_api(&format!("{}/comments?per_page={}&page={}", api, RECENT_COMMENTS, page))?;
+            comments.extend(listed.as_array().cloned().unwrap_or_default());
+        }
+        section.push_str(&format!("\nRecent Comments ({} of {}):\n", comments.len().min(RECENT_COMMENTS), total));
+        for comment in comments.iter().rev().take(RECENT_COMMENTS).rev() {
+            section.push_str(&format!(
+                "\n--- {} ({}) ---\n{}\n",
+                comment["user"]["login"].as_str().unwrap_or("unknown"),
+                comment["created_at"].as_str().unwrap_or(""),
+                comment["body"].as_str().unwrap_or("").trim()
+            ));
+        }
+    }
+    Ok(section)
+}
+
+// Comments come oldest first, in pages of RECENT_COMMENTS: the recent ones are on the last
+// page, plus the one before it when the last page is only partly filled
+fn recent_pages(total: usize) -> Vec<usize> {
+    let last = total.div_ceil(RECENT_COMMENTS);
+    if last > 1 && !total.is_multiple_of(RECENT_COMMENTS) {
+        vec![last - 1, last]
+    } else {
+        vec![last]
+    }
+}
+
+// https://github.com/org/repo/issues/7 (or /pull/7, same number space)
+fn issue_reference(reference: &str) -> Option<(String, String, u64)> {
+    let rest = reference.strip_prefix("https://github.com/").or_else(|| reference.strip_prefix("http://github.com/"))?;
+    let mut parts = rest.trim_end_matches('/').split('/');
+    let (owner, repo, kind, number) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if !matches!(kind, "issues" | "pull") {
+        return None;
+    }
+    Some((owner.to_string(), repo.to_string(), number.parse().ok()?))
+}
+
+// Owner and name from https://github.com/org/repo(.git) or git@github.com:org/repo(.git)
+fn github_slug(url: &str) -> Option<(String, String)> {
+    let rest = url
+        .strip_prefix("https://github.com/")
+        .or_else(|| url.strip_prefix("http://github.com/"))
+        .or_else(|| url.strip_prefix("git@github.com:"))
+        .or_else(|| url.strip_prefix("ssh://git@github.com/"))?;
+    let (owner, repo) = rest.trim_end_matches('/').split_once('/')?;
+    Some((owner.to_string(), repo.trim_end_matches(".git").to_string()))
+}
+
+// HTTP GET through curl, which is already where git is. The token header (if any) goes
+// through stdin and only to GitHub hosts. Returns the body, or the curl error text.
+pub fn http_get(url: &str, accept: &str) -> Result<std::result::Result<Vec<u8>, String>> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let accept = format!("Accept: {}", accept);
+    let mut child = Command::new("curl")
+        .args(["-sSfL", "-H", accept.as_str(), "-H", "User-Agent: gimtex", "-H", "@-"])
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to execute curl")?;
+    let github = ["https://api.github.com/", "https://raw.githubusercontent.com/", "https://gist.githubusercontent.com/"];
+    let auth = match token() {
+        Some(t) if github.iter().any(|host| url.starts_with(host)) => format!("Authorization: Bearer {}\n", t),
+        _ => String::new(),
+    };
+    child.stdin.take().context("curl stdin unavailable")?.write_all(auth.as_bytes())?;
+    let output = child.wait_with_output().context("Failed to execute curl")?;
+    if !output.status.success() {
+        return Ok(Err(String::from_utf8_lossy(&output.stderr).trim_end().to_string()));
+    }
+    Ok(Ok(output.stdout))
+}
+
+// GitHub REST call
+fn github_api(path: &str) -> Result<serde_json::Value> {
+    let url = format!("https://api.github.com/{}", path);
+    let body = match http_get(&url, "application/vnd.github+json")? {
+        Ok(body) => body,
+        Err(stderr) => return Err(fetch_failure(&format!("GitHub API request failed: {}", url), &stderr)),
+    };
+    serde_json::from_slice(&body).with_context(|| format!("Unexpected GitHub API response from {}", url))
+}
+
+// SNIPPET TARGETS
+// Raw-file URLs (and GitHub/GitLab `blob` pages) and gists are downloaded, not cloned:
+// the files land in a temp dir and go through the normal pipeline as a tiny tree.
+
+pub fn is_snippet(target: &str) -> bool {
+    gist_id(target).is_some() || raw_file_url(target).is_some()
+}
+
+fn gist_id(target: &str) -> Option<&str> {
+    let rest = target.strip_prefix("https://gist.github.com/")?;
+    let id = rest.trim_end_matches('/').rsplit('/').next()?;
+    (!id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit())).then_some(id)
+}
+
+// Browser `blob` views map onto their raw endpoints
+fn raw_file_url(target: &str) -> Option<String> {
+    if target.starts_with("https://raw.githubusercontent.com/") || target.starts_with("https://gist.githubusercontent.com/") {
+        return Some(target.to_string());
+    }
+    if let Some(rest) = target.strip_prefix("https://github.com/") {
+        let parts: Vec<&str> = rest.splitn(4, '/').collect();
+        if let [owner, repo, "blob", tail] = parts.as_slice() {
+            return Some(format!("https://raw.githubusercontent.com/{}/{}/{}", owner, repo, tail));
+        }
+    }
+    if target.starts_with("https://") {
+        if target.contains("/-/raw/") {
+            return Some(target.to_string());
+        }
+        if target.contains("/-/blob/") {
+            return Some(target.replacen("/-/blob/", "/-/raw/", 1));
+        }
+    }
+    None
+}
+
+pub fn download_snippet(target: &str) -> Result<TempDir> {
+    let dir = tempfile::Builder::new()
+        .prefix("gimtex_snippet")
+        .tempdir()
+        .context("Failed to create temporary bunker")?;
+
+    let mut files: Vec<(String, Vec<u8>)> = Vec::new();
+    if let Some(id) = gist_id(target) {
+        let gist = github_api(&format!("gists/{}", id))?;
+        let Some(entries) = gist["files"].as_object() else {
+            anyhow::bail!("Gist {} has no files", id);
+        };
+        for (name, file) in entries {
+            // Large gist files come back truncated; their raw_url has the full text
+            let content = match (file["truncated"].as_bool(), file["raw_url"].as_str()) {
+                (Some(true), Some(raw)) => match http_get(raw, "*/*")? {
+                    Ok(body) => body,
+                    Err(e) => return Err(fetch_failure(&format!("Download failed: {}", raw), &e)),
+                },
+                _ => file["content"].as_str().unwrap_or_default().as_bytes().to_vec(),
+            };
+            files.push((name.clone(), content));
+        }
+    } else if let Some(url) = raw_file_url(target) {
+        let name = url.split(['?', '#']).next().unwrap_or(&url).rsplit('/').next().unwrap_or("snippet").to_string();
+        match http_get(&url, "*/*")? {
+            Ok(body) => files.push((name, body)),
+            Err(e) => return Err(fetch_failure(&format!("Download failed: {}", url), &e)),
+        }
+    }
+
+    for (name, content) in &files {
+        // Names come from the network: keep only the final component
+        let name = Path::new(name).file_name().map(|n| n.to_os_string()).unwrap_or_else(|| "snippet".into());
+        std::fs::write(dir.path().join(name), content).context("Failed to write downloaded snippet")?;
+    }
+    crate::status!("{} Downloaded {} file(s) from {}", "[OK]".green().bold(), files.len(), target);
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fetch_failures_carry_stderr_and_the_auth_hint() {
+        let err = fetch_failure("Could not clone https://github.com/o/r.git", "remote: Repository not found.\nfatal: repository not found\n").to_string();
+        assert!(err.starts_with("Could not clone https://github.com/o/r.git\nremote: Repository not found."), "{}", err);
+        assert!(err.contains("Authentication required: "), "{}", err);
+
+        let err = fetch_failure("Download failed: https://x/y.zip", "curl: (7) Failed to connect").to_string();
+        assert!(err.ends_with("curl: (7) Failed to connect"), "{}", err);
+    }
+
+    #[test]
+    fn recent_pages_cover_the_last_comments() {
+        assert_eq!(recent_pages(1), [1]);
+        assert_eq!(recent_pages(RECENT_COMMENTS), [1]);
+        assert_eq!(recent_pages(RECENT_COMMENTS + 1), [1, 2]);
+        assert_eq!(recent_pages(3 * RECENT_COMMENTS), [3]);
+        assert_eq!(recent_pages(3 * RECENT_COMMENTS + 2), [3, 4]);
+        // The pages always hold at least RECENT_COMMENTS comments when there are that many
+        for total in RECENT_COMMENTS..200 {
+            let pages = recent_pages(total);
+            let last_count = total - (pages[pages.len() - 1] - 1) * RECENT_COMMENTS;
+            assert!(last_count + (pages.len() - 1) * RECENT_COMMENTS >= RECENT_COMMENTS, "{}", total);
+        }
+    }
+
+    #[test]
+    fn unreachable_clone_is_an_error() {
+        let spec = RemoteSpec::plain("https://127.0.0.1:1/missing.git");
+        assert!(clone(&spec).unwrap_err().to_string().starts_with("Could not clone https://127.0.0.1:1/missing.git"));
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// SAVED SELECTIONS
+// `--save-selection` writes the picked files as JSON, paths relative to the scan root with
+// `/` separators, so the file can be committed and replayed on any checkout through
+// `--selection`. Every interactive pick is also remembered per scan root under
+// ~/.local/state/gimtex/selections/, and offered back the next time -I starts there.
+
+#[derive(Serialize, Deserialize)]
+struct Selection {
+    files: Vec<String>,
+}
+
+pub fn save(target: &Path, files: &[PathBuf], root: &Path) -> Result<()> {
+    write(target, files, root)?;
+    crate::status!("{} Selection saved: {} ({} files)", "[OK]".green().bold(), target.display().to_string().yellow(), files.len());
+    Ok(())
+}
+
+fn write(target: &Path, files: &[PathBuf], root: &Path) -> Result<()> {
+    let selection = Selection {
+        files: files.iter().map(|f| f.strip_prefix(root).unwrap_or(f).to_string_lossy().replace('\\', "/")).collect(),
+    };
+    let text = serde_json::to_string_pretty(&selection).context("Failed to serialize selection")?;
+    std::fs::write(target, text + "\n").with_context(|| format!("Failed to write selection: {}", target.display()))
+}
+
+// Canonical paths of the saved files; entries that no longer exist are reported and skipped
+pub fn load(source: &Path, root: &Path) -> Result<HashSet<PathBuf>> {
+    let text = std::fs::read_to_string(source).with_context(|| format!("Failed to read selection: {}", source.display()))?;
+    let selection: Selection = serde_json::from_str(&text).with_context(|| format!("Invalid selection file: {}", source.display()))?;
+    let (found, missing) = resolve(&selection, root);
+    if !missing.is_empty() {
+        crate::status!("{} {} selected file(s) no longer exist: {}", "[!]".yellow().bold(), missing.len(), missing.join(", "));
+    }
+    Ok(found)
+}
+
+// (canonical paths that exist, entries that don't)
+fn resolve<'a>(selection: &'a Selection, root: &Path) -> (HashSet<PathBuf>, Vec<&'a str>) {
+    let mut found = HashSet::new();
+    let mut missing = Vec::new();
+    for file in &selection.files {
+        match root.join(file).canonicalize() {
+            Ok(canonical) => {
+                found.insert(canonical);
+            }
+            Err(_) => missing.push(file.as_str()),
+        }
+    }
+    (found, missing)
+}
+
+fn state_root() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state")))?;
+    Some(base.join("gimtex").join("selections"))
+}
+
+// FNV-1a of the canonical root, like the clone cache keys
+fn state_file(root: &Path) -> Option<PathBuf> {
+    let root = root.canonicalize().ok()?;
+    let hash = crate::fnv::fnv1a(root.to_string_lossy().as_bytes());
+    Some(state_root()?.join(format!("{:016x}.json", hash)))
+}
+
+// The last interactive pick under this root, minus files that are gone; None when there is none
+pub fn remembered(root: &Path) -> Option<HashSet<PathBuf>> {
+    let text = std::fs::read_to_string(state_file(root)?).ok()?;
+    let selection: Selection = serde_json::from_str(&text).ok()?;
+    let (found, _) = resolve(&selection, root);
+    (!found.is_empty()).then_some(found)
+}
+
+// Best effort: a read-only home shouldn't fail the run that just produced a payload
+pub fn remember(root: &Path, files: &[PathBuf]) {
+    let Some(target) = state_file(root) else { return };
+    let stored = target.parent().is_some_and(|dir| std::fs::create_dir_all(dir).is_ok()) && write(&target, files, root).is_ok();
+    if !stored {
+        crate::status!("{} Could not remember this selection in {}", "[!]".yellow().bold(), target.display());
+    }
+}
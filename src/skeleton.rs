@@ -0,0 +1,280 @@
+use regex::Regex;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// SKELETONS
+// Declarations without bodies: what a file offers to its callers, at a fraction of the tokens.
+// Line-based on purpose: brace languages track block nesting and collapse function bodies
+// to `{ ... }`, Python keeps `class`/`def` headers by indentation.
+
+enum Language {
+    Brace,
+    Python,
+}
+
+fn language(path: &Path) -> Option<Language> {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "go" | "java" | "kt"
+        | "swift" | "cs" | "scala" | "php" | "dart" => Some(Language::Brace),
+        "py" => Some(Language::Python),
+        _ => None,
+    }
+}
+
+// None when the language has no skeleton rules; callers fall back to the full text
+pub fn skeleton(path: &Path, content: &str) -> Option<String> {
+    Some(match language(path)? {
+        Language::Brace => brace_skeleton(content),
+        Language::Python => python_skeleton(content),
+    })
+}
+
+// `--symbol`: where a name is declared. Keyword forms (`fn x`, `class X`, `func (r T) X`,
+// `const x =`) are tried first, then C-family signatures (`int x(`, `public void x(`)
+// that carry no keyword at all.
+pub struct Definitions {
+    keyword: Regex,
+    signature: Regex,
+    python: Regex,
+}
+
+impl Definitions {
+    pub fn new(name: &str) -> Self {
+        let name = regex::escape(name);
+        let keyword = format!(
+            r"^\s*(?:[\w()\[\]#@:<>,]+\s+)*?(?:fn|func|function|fun|struct|enum|union|trait|type|class|interface|record|object|protocol|extension|mod|namespace|const|static|macro_rules!)\s+(?:\([^)]*\)\s*)?\*?{}\b",
+            name
+        );
+        let signature = format!(
+            r"^\s*(?:[\w:<>*&,\[\]]+\s+)+[*&]?{}\s*\((?:[^;]*$|.*\)\s*\{{)",
+            name
+        );
+        let python = format!(r"^(?:\s*(?:async\s+)?(?:def|class)\s+{}\b|{}\s*(?::[^=]*)?=)", name, name);
+        Self {
+            keyword: Regex::new(&keyword).expect("escaped symbol pattern"),
+            signature: Regex::new(&signature).expect("escaped symbol pattern"),
+            python: Regex::new(&python).expect("escaped symbol pattern"),
+        }
+    }
+
+    // 1-based line of the first declaration in the file
+    pub fn line(&self, path: &Path, content: &str) -> Option<usize> {
+        let find = |re: &Regex| content.lines().position(|line| re.is_match(line) && !is_call(line)).map(|i| i + 1);
+        match language(path)? {
+            Language::Brace => find(&self.keyword).or_else(|| find(&self.signature)),
+            Language::Python => find(&self.python),
+        }
+    }
+}
+
+// `return x(...)` and friends have the shape of a signature, but call the function
+fn is_call(line: &str) -> bool {
+    const CALLERS: [&str; 7] = ["return", "new", "await", "throw", "else", "case", "yield"];
+    line.split_whitespace().next().is_some_and(|first| CALLERS.contains(&first))
+}
+
+// `--no-tests`: inline `#[cfg(test)] mod tests { ... }` blocks cut from Rust sources
+pub fn without_test_modules(content: &str) -> String {
+    static MODULE: OnceLock<Regex> = OnceLock::new();
+    let module_re = regex(&MODULE, r"^\s*(?:pub(?:\([\w:]+\))?\s+)?mod\s+\w+\s*\{");
+
+    let mut out = String::new();
+    let mut in_comment = false;
+    let mut lines = content.lines().peekable();
+    while let Some(line) = lines.next() {
+        if line.trim() == "#[cfg(test)]" && !in_comment {
+            let mut attributes = vec![line];
+            while let Some(next) = lines.next_if(|l| l.trim_start().starts_with("#[")) {
+                attributes.push(next);
+            }
+            if lines.peek().is_some_and(|l| module_re.is_match(l)) {
+                let mut depth = 0usize;
+                for block_line in lines.by_ref() {
+                    let code = strip_code(block_line, &mut in_comment);
+                    depth = (depth + code.matches('{').count()).saturating_sub(code.matches('}').count());
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                continue;
+            }
+            for attribute in attributes {
+                out.push_str(attribute);
+                out.push('\n');
+            }
+            continue;
+        }
+        strip_code(line, &mut in_comment);
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+// `--map`: the skeleton without docs, imports and blank lines, for a whole-repo overview
+pub fn map(path: &Path, content: &str) -> Option<String> {
+    const SKIPPED: [&str; 12] = ["///", "//!", "/**", "*", "//", "use ", "import ", "from ", "package ", "#include", "using ", "extern crate "];
+    let skeleton = skeleton(path, content)?;
+    let mut out = String::new();
+    for line in skeleton.lines() {
+        let trimmed = line.trim_start();
+        if !trimmed.is_empty() && !SKIPPED.iter().any(|prefix| trimmed.starts_with(prefix)) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+enum Block {
+    // Type-level scopes (impl, class, trait, struct...) stay visible
+    Container,
+    // Everything else (function bodies, initializers) is hidden until it closes
+    Body,
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("static skeleton pattern"))
+}
+
+fn brace_skeleton(content: &str) -> String {
+    static FUNCTION: OnceLock<Regex> = OnceLock::new();
+    static CONTAINER: OnceLock<Regex> = OnceLock::new();
+    let function_re = regex(&FUNCTION, r"^(?:[\w()]+\s+)*(?:fn|func|function|def|fun)\b");
+    let container_re = regex(
+        &CONTAINER,
+        r"^[^(=]*\b(?:impl|trait|mod|struct|enum|union|class|interface|namespace|object|extension|protocol|record)\b",
+    );
+
+    let mut out = String::new();
+    let mut stack: Vec<(Block, usize)> = Vec::new();
+    let mut depth = 0usize;
+    let mut in_comment = false;
+
+    for line in content.lines() {
+        let code = strip_code(line, &mut in_comment);
+        let opens = code.matches('{').count();
+        let closes = code.matches('}').count();
+        let hidden = stack.iter().any(|(block, _)| matches!(block, Block::Body));
+        let trimmed = line.trim();
+
+        if !hidden && !trimmed.is_empty() {
+            let is_doc = trimmed.starts_with("///") || trimmed.starts_with("//!") || trimmed.starts_with("/**") || trimmed.starts_with('*');
+            let is_comment = trimmed.starts_with("//") || trimmed.starts_with("/*");
+            if opens > closes {
+                let head = code.trim_start();
+                if container_re.is_match(head) && !function_re.is_match(head) {
+                    out.push_str(line);
+                    out.push('\n');
+                    stack.push((Block::Container, depth));
+                } else {
+                    // Collapse the body but keep the signature up to its opening brace
+                    let brace = line.find('{').unwrap_or(line.len());
+                    out.push_str(line[..brace].trim_end());
+                    out.push_str(" { ... }\n");
+                    stack.push((Block::Body, depth));
+                }
+            } else if is_doc || !is_comment {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        depth = (depth + opens).saturating_sub(closes);
+        while stack.last().is_some_and(|(_, start)| depth <= *start) {
+            stack.pop();
+        }
+    }
+    out
+}
+
+// Code part of a line for brace counting: no string/char literals, no comments
+fn strip_code(line: &str, in_comment: &mut bool) -> String {
+    let mut code = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if *in_comment {
+            if c == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                *in_comment = false;
+            }
+            continue;
+        }
+        match c {
+            '/' if chars.peek() == Some(&'/') => break,
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                *in_comment = true;
+            }
+            '"' | '`' => {
+                while let Some(s) = chars.next() {
+                    if s == '\\' {
+                        chars.next();
+                    } else if s == c {
+                        break;
+                    }
+                }
+            }
+            // Char literals ('{', '\n') but not Rust lifetimes ('a)
+            '\'' => {
+                let rest: String = chars.clone().take(3).collect();
+                if rest.starts_with('\\') {
+                    chars.next();
+                    chars.next();
+                    for s in chars.by_ref() {
+                        if s == '\'' {
+                            break;
+                        }
+                    }
+                } else if rest.chars().nth(1) == Some('\'') {
+                    chars.next();
+                    chars.next();
+                }
+            }
+            _ => code.push(c),
+        }
+    }
+    code
+}
+
+fn python_skeleton(content: &str) -> String {
+    static HEADER: OnceLock<Regex> = OnceLock::new();
+    static TOP_LEVEL: OnceLock<Regex> = OnceLock::new();
+    let header_re = regex(&HEADER, r"^\s*(?:@|(?:async\s+)?def\b|class\b)");
+    let top_level_re = regex(&TOP_LEVEL, r"^(?:import\b|from\b|[A-Za-z_][\w.]*\s*(?::[^=]*)?=)");
+
+    let mut out = String::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        if header_re.is_match(line) {
+            // Signatures may span lines: continue until the brackets balance and the line ends in ':'
+            let mut header = line.to_string();
+            let mut balance = bracket_balance(line);
+            while balance > 0 || !(header.trim_end().ends_with(':') || header.trim_start().starts_with('@')) {
+                let Some(next) = lines.next() else { break };
+                header.push('\n');
+                header.push_str(next);
+                balance += bracket_balance(next);
+            }
+            out.push_str(&header);
+            out.push('\n');
+            if line.trim_start().starts_with("def") || line.trim_start().starts_with("async") {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                out.push_str(&format!("{}    ...\n", indent));
+            }
+        } else if top_level_re.is_match(line) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn bracket_balance(line: &str) -> i32 {
+    let code = line.split('#').next().unwrap_or("");
+    code.chars().map(|c| match c {
+        '(' | '[' | '{' => 1,
+        ')' | ']' | '}' => -1,
+        _ => 0,
+    }).sum()
+}
@@ -0,0 +1,255 @@
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// RUN STATISTICS
+// Every file that leaves a run lands in one ledger with its reason, whichever stage
+// dropped it: the walker, the selection filters or the parallel workers. The ledger is
+// process-wide, like the quiet and interrupt flags, so no stage needs a handle threaded
+// through it. `--stats-json` writes its totals with the payload metrics and stage times.
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    Rules,
+    Tests,
+    NotTests,
+    MinSize,
+    Scope,
+    Focus,
+    Symbol,
+    Since,
+    Author,
+    Filter,
+    Exclude,
+    MaxFiles,
+    Selection,
+    Generated,
+    Sensitive,
+    Dotenv,
+    NoDeclarations,
+    Grep,
+    Budget,
+    Binary,
+    TooLarge,
+    Lfs,
+    NoText,
+    Symlink,
+    Unreadable,
+    Interrupted,
+}
+
+impl Reason {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Reason::Rules => "excluded by gimtex.toml [rules]",
+            Reason::Tests => "test file (--no-tests)",
+            Reason::NotTests => "not a test file (--only-tests)",
+            Reason::MinSize => "under --min-size",
+            Reason::Scope => "outside the chosen files",
+            Reason::Focus => "not reached from the --expand-imports seeds",
+            Reason::Symbol => "never mentions the --symbol",
+            Reason::Since => "unchanged since --since",
+            Reason::Author => "last touched by another --author",
+            Reason::Filter => "matches no --filter",
+            Reason::Exclude => "matches an --exclude",
+            Reason::MaxFiles => "over --max-files",
+            Reason::Selection => "not in the --selection",
+            Reason::Generated => "generated or vendored",
+            Reason::Sensitive => "sensitive (key material or credentials)",
+            Reason::Dotenv => "dotenv file",
+            Reason::NoDeclarations => "no declarations for --map",
+            Reason::Grep => "no --grep match",
+            Reason::Budget => "over the --max-tokens budget",
+            Reason::Binary => "binary",
+            Reason::TooLarge => "too large (--max-size)",
+            Reason::Lfs => "LFS object not fetched",
+            Reason::NoText => "no extractable text",
+            Reason::Symlink => "symlink outside the root or in a loop",
+            Reason::Unreadable => "unreadable",
+            Reason::Interrupted => "not processed before Ctrl+C",
+        }
+    }
+
+    // Files lost to the run rather than to a filter the user asked for
+    fn noteworthy(self) -> bool {
+        self.incomplete() || matches!(self, Reason::Binary | Reason::Symlink)
+    }
+
+    // Text that should have been in the payload and isn't: what --strict fails on.
+    // Binaries and the symlink guard are by design, the same as the filters.
+    fn incomplete(self) -> bool {
+        matches!(self, Reason::TooLarge | Reason::Lfs | Reason::NoText | Reason::Unreadable | Reason::Interrupted)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct Skip {
+    pub path: PathBuf,
+    pub reason: Reason,
+    // The size, or the error that made the file unreadable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+static SKIPS: Mutex<Vec<Skip>> = Mutex::new(Vec::new());
+
+pub fn record(reason: Reason, path: &Path) {
+    SKIPS.lock().unwrap_or_else(|e| e.into_inner()).push(Skip { reason, path: path.to_path_buf(), detail: None });
+}
+
+pub fn record_detail(reason: Reason, path: &Path, detail: String) {
+    SKIPS.lock().unwrap_or_else(|e| e.into_inner()).push(Skip { reason, path: path.to_path_buf(), detail: Some(detail) });
+}
+
+// A scan starts from an empty ledger (the tui runs several in one process)
+pub fn reset() {
+    SKIPS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+// Sorted by path: the parallel workers record in no particular order
+pub fn skips() -> Vec<Skip> {
+    let mut skips = SKIPS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    skips.sort_by(|a, b| a.path.cmp(&b.path));
+    skips
+}
+
+// --strict: an error naming every file the payload would silently lack
+pub fn enforce_strict(root: &Path) -> Result<()> {
+    let missing: Vec<String> = skips()
+        .iter()
+        .filter(|s| s.reason.incomplete())
+        .map(|s| {
+            let path = s.path.strip_prefix(root).unwrap_or(&s.path).display().to_string();
+            match s.detail {
+                Some(ref detail) => format!("    {}: {} ({})", path, s.reason.describe(), detail),
+                None => format!("    {}: {}", path, s.reason.describe()),
+            }
+        })
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    anyhow::bail!("--strict: {} file(s) could not be included, nothing was written:\n{}", missing.len(), missing.join("\n"));
+}
+
+// Examples named per reason in the closing summary, unless --show-skips
+const SUMMARY_EXAMPLES: usize = 3;
+
+// Closing summary: what the run lost, grouped by reason, after the dashboard instead of
+// scrolled away between progress lines
+pub fn print_summary(root: &Path, show_all: bool) {
+    let skips = skips();
+    let mut groups: BTreeMap<Reason, Vec<&Skip>> = BTreeMap::new();
+    for skip in skips.iter().filter(|s| s.reason.noteworthy()) {
+        groups.entry(skip.reason).or_default().push(skip);
+    }
+    if groups.is_empty() {
+        return;
+    }
+    let total: usize = groups.values().map(Vec::len).sum();
+    let hint = if show_all { "" } else { " (--show-skips lists them all)" };
+    crate::status!("{} Skipped {} file(s){}:", "[!]".yellow().bold(), total, hint);
+    let width = groups.keys().map(|r| r.describe().len()).max().unwrap_or(0);
+    for (reason, group) in &groups {
+        let label = |skip: &Skip| {
+            let path = skip.path.strip_prefix(root).unwrap_or(&skip.path).display().to_string();
+            match skip.detail {
+                Some(ref detail) => format!("{} ({})", path, detail),
+                None => path,
+            }
+        };
+        if show_all {
+            crate::status!("    {:>5}  {}", group.len(), reason.describe());
+            for skip in group {
+                crate::status!("           {}", label(skip).dimmed());
+            }
+            continue;
+        }
+        let mut examples: Vec<String> = group.iter().take(SUMMARY_EXAMPLES).map(|s| label(s)).collect();
+        if group.len() > SUMMARY_EXAMPLES {
+            examples.push(format!("+{} more", group.len() - SUMMARY_EXAMPLES));
+        }
+        crate::status!("    {:>5}  {:<width$}  {}", group.len(), reason.describe(), examples.join(", ").dimmed(), width = width);
+    }
+}
+
+#[derive(Serialize)]
+struct Stage {
+    name: &'static str,
+    ms: f64,
+    files: usize,
+}
+
+// Wall time per stage: each `stage` call closes the one running since the previous call
+pub struct Clock {
+    started: Instant,
+    last: Instant,
+    stages: Vec<Stage>,
+}
+
+impl Clock {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self { started: now, last: now, stages: Vec::new() }
+    }
+
+    pub fn stage(&mut self, name: &'static str, files: usize) {
+        let elapsed = self.last.elapsed();
+        log::info!("stage: {} took {:.1?} ({} files)", name, elapsed, files);
+        self.stages.push(Stage { name, ms: elapsed.as_secs_f64() * 1000.0, files });
+        self.last = Instant::now();
+    }
+}
+
+#[derive(Serialize)]
+struct Files {
+    // emitted + skipped: every file the run saw, whatever stage dropped it
+    scanned: usize,
+    emitted: usize,
+    skipped: BTreeMap<Reason, usize>,
+    // Every skipped file with its reason, by path
+    skipped_files: Vec<Skip>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    files: Files,
+    // null under --no-tokens
+    tokens: Option<usize>,
+    chars: usize,
+    secrets: usize,
+    stages: &'a [Stage],
+    wall_ms: f64,
+}
+
+pub struct Totals {
+    pub emitted: usize,
+    pub tokens: Option<usize>,
+    pub chars: usize,
+    pub secrets: usize,
+}
+
+pub fn write(target: &Path, clock: &Clock, totals: Totals) -> Result<()> {
+    let skipped_files = skips();
+    let mut skipped = BTreeMap::new();
+    for skip in &skipped_files {
+        *skipped.entry(skip.reason).or_insert(0) += 1;
+    }
+    let report = Report {
+        files: Files { scanned: totals.emitted + skipped_files.len(), emitted: totals.emitted, skipped, skipped_files },
+        tokens: totals.tokens,
+        chars: totals.chars,
+        secrets: totals.secrets,
+        stages: &clock.stages,
+        wall_ms: clock.started.elapsed().as_secs_f64() * 1000.0,
+    };
+    let text = serde_json::to_string_pretty(&report).context("Failed to serialize run statistics")?;
+    std::fs::write(target, text + "\n").with_context(|| format!("Failed to write run statistics: {}", target.display()))?;
+    crate::status!("{} Run statistics written to: {}", "[OK]".green().bold(), target.display().to_string().yellow());
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use regex::Regex;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// STRUCTURED SECRETS
+// Config formats hide credentials the line regexes can't see: unquoted YAML values,
+// block scalars, TOML multi-line strings. These are parsed (JSON/TOML) or walked by
+// indentation (YAML, no parser in the tree) and the values of credential-looking keys
+// are returned as byte ranges of the original text.
+
+// Anything shorter is a placeholder or a flag, not a credential
+const MIN_VALUE_LEN: usize = 4;
+
+// Key names are split into words (`db_password`, `apiKey`, `secret-token`) so that
+// `tokenizer` or `keyboard` don't count
+fn is_sensitive_key(key: &str) -> bool {
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in key.chars() {
+        if !c.is_alphanumeric() {
+            words.push(std::mem::take(&mut current));
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        current.extend(c.to_lowercase());
+    }
+    words.push(current);
+    words.iter().any(|w| matches!(w.as_str(), "password" | "passwd" | "pwd" | "secret" | "token" | "key" | "apikey" | "passphrase"))
+}
+
+// Env references and templates point at a secret, they aren't one
+fn is_literal(value: &str) -> bool {
+    value.len() >= MIN_VALUE_LEN
+        && !value.starts_with("${")
+        && !value.starts_with("{{")
+        && !matches!(value, "true" | "false" | "null" | "~")
+        && value.parse::<f64>().is_err()
+}
+
+pub fn secret_ranges(path: &Path, content: &str) -> Vec<Range<usize>> {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "json" => match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(doc) => {
+                let mut values = Vec::new();
+                json_values(&doc, false, &mut values);
+                locate(content, &values)
+            }
+            Err(_) => Vec::new(),
+        },
+        "toml" => match content.parse::<toml::Value>() {
+            Ok(doc) => {
+                let mut values = Vec::new();
+                toml_values(&doc, false, &mut values);
+                locate(content, &values)
+            }
+            Err(_) => Vec::new(),
+        },
+        "yml" | "yaml" => yaml_ranges(content),
+        _ => Vec::new(),
+    }
+}
+
+fn json_values(value: &serde_json::Value, sensitive: bool, out: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) if sensitive && is_literal(s) => out.push(s.clone()),
+        serde_json::Value::Array(items) => items.iter().for_each(|v| json_values(v, sensitive, out)),
+        serde_json::Value::Object(map) => map.iter().for_each(|(k, v)| json_values(v, is_sensitive_key(k), out)),
+        _ => {}
+    }
+}
+
+fn toml_values(value: &toml::Value, sensitive: bool, out: &mut Vec<String>) {
+    match value {
+        toml::Value::String(s) if sensitive && is_literal(s) => out.push(s.clone()),
+        toml::Value::Array(items) => items.iter().for_each(|v| toml_values(v, sensitive, out)),
+        toml::Value::Table(map) => map.iter().for_each(|(k, v)| toml_values(v, is_sensitive_key(k), out)),
+        _ => {}
+    }
+}
+
+// Parsed values back to source positions: JSON-escaped (covers TOML basic strings too),
+// plus verbatim, or line by line for multi-line values so the line structure survives
+fn locate(content: &str, values: &[String]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    for value in values {
+        let escaped = serde_json::to_string(value).unwrap_or_default();
+        let mut forms = vec![escaped.trim_matches('"')];
+        if value.contains('\n') {
+            forms.extend(value.lines().map(str::trim).filter(|l| l.len() >= MIN_VALUE_LEN));
+        } else {
+            forms.push(value.as_str());
+        }
+        for form in forms {
+            ranges.extend(content.match_indices(form).map(|(start, m)| start..start + m.len()));
+        }
+    }
+    ranges
+}
+
+fn yaml_ranges(content: &str) -> Vec<Range<usize>> {
+    static ENTRY: OnceLock<Regex> = OnceLock::new();
+    let entry_re = ENTRY.get_or_init(|| {
+        Regex::new(r#"^(\s*)(?:-\s+)?["']?([\w.-]+)["']?\s*:(?:\s+(.*?))?\s*$"#).expect("static yaml pattern")
+    });
+
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        lines.push((offset, line.trim_end_matches(['\n', '\r'])));
+        offset += line.len();
+    }
+
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (start, line) = lines[i];
+        i += 1;
+        let Some(caps) = entry_re.captures(line) else { continue };
+        if !is_sensitive_key(&caps[2]) {
+            continue;
+        }
+        let Some(value) = caps.get(3) else { continue };
+        let text = value.as_str();
+
+        // Block scalars (`|`, `>-`...): every deeper-indented line belongs to the value
+        if text.starts_with('|') || text.starts_with('>') {
+            let indent = caps[1].len();
+            while i < lines.len() {
+                let (line_start, block_line) = lines[i];
+                let trimmed = block_line.trim_start();
+                if !trimmed.is_empty() && block_line.len() - trimmed.len() <= indent {
+                    break;
+                }
+                if !trimmed.is_empty() {
+                    let begin = line_start + block_line.len() - trimmed.len();
+                    ranges.push(begin..begin + trimmed.trim_end().len());
+                }
+                i += 1;
+            }
+            continue;
+        }
+
+        // Flow collections, anchors and aliases are structure, not values
+        if text.starts_with(['{', '[', '&', '*', '#']) {
+            continue;
+        }
+        let (inner, inner_offset) = match text.chars().next() {
+            Some(q @ ('"' | '\'')) => match text[1..].find(q) {
+                Some(end) => (&text[1..1 + end], 1),
+                None => continue,
+            },
+            _ => {
+                let unquoted = text.find(" #").map_or(text, |comment| text[..comment].trim_end());
+                (unquoted, 0)
+            }
+        };
+        if is_literal(inner) {
+            let begin = start + value.start() + inner_offset;
+            ranges.push(begin..begin + inner.len());
+        }
+    }
+    ranges
+}
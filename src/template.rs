@@ -0,0 +1,123 @@
+use anyhow::Result;
+
+// Output Templates: a small Handlebars-flavoured renderer.
+//
+// Globals:   {{root}} {{tree}} {{project_context}} {{files}} {{file_count}} {{total_tokens}}
+// Per file:  {{#each files}} ... {{path}} {{language}} {{tokens}} {{fence}} {{content}} ... {{/each}}
+//
+// `{{fence}}` is a backtick run longer than any inside the content, so markdown code
+// blocks built from it hold files that contain fences of their own.
+
+pub struct TemplateFile {
+    pub path: String,
+    pub language: String,
+    pub tokens: usize,
+    pub fence: String,
+    pub content: String,
+}
+
+pub struct TemplateContext {
+    pub root: String,
+    pub tree: String,
+    pub project_context: String,
+    pub files: Vec<TemplateFile>,
+    pub total_tokens: usize,
+}
+
+const EACH_OPEN: &str = "{{#each files}}";
+const EACH_CLOSE: &str = "{{/each}}";
+
+pub fn render(template: &str, ctx: &TemplateContext) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find(EACH_OPEN) {
+        out.push_str(&substitute(&rest[..start], |name| global(ctx, name))?);
+        let body_start = start + EACH_OPEN.len();
+        let Some(len) = rest[body_start..].find(EACH_CLOSE) else {
+            anyhow::bail!("Template error: `{}` without matching `{}`", EACH_OPEN, EACH_CLOSE);
+        };
+        let body = &rest[body_start..body_start + len];
+        for file in &ctx.files {
+            out.push_str(&substitute(body, |name| per_file(file, name).or_else(|| global(ctx, name)))?);
+        }
+        rest = &rest[body_start + len + EACH_CLOSE.len()..];
+    }
+    out.push_str(&substitute(rest, |name| global(ctx, name))?);
+
+    Ok(out)
+}
+
+fn global(ctx: &TemplateContext, name: &str) -> Option<String> {
+    match name {
+        "root" => Some(ctx.root.clone()),
+        "tree" => Some(ctx.tree.clone()),
+        "project_context" => Some(ctx.project_context.clone()),
+        "file_count" => Some(ctx.files.len().to_string()),
+        "total_tokens" => Some(ctx.total_tokens.to_string()),
+        "files" => Some(default_files(ctx)),
+        _ => None,
+    }
+}
+
+fn per_file(file: &TemplateFile, name: &str) -> Option<String> {
+    match name {
+        "path" => Some(file.path.clone()),
+        "language" => Some(file.language.clone()),
+        "tokens" => Some(file.tokens.to_string()),
+        "fence" => Some(file.fence.clone()),
+        "content" => Some(file.content.clone()),
+        _ => None,
+    }
+}
+
+// `{{files}}` without a loop: the stock markdown framing, minus colors.
+fn default_files(ctx: &TemplateContext) -> String {
+    let mut out = String::new();
+    for file in &ctx.files {
+        out.push_str(&format!("--- File: {} ({} tokens) ---\n{}{}\n{}\n{}\n\n", file.path, file.tokens, file.fence, file.language, file.content, file.fence));
+    }
+    out
+}
+
+fn substitute(text: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find("}}") else {
+            anyhow::bail!("Template error: unclosed `{{{{`");
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        match lookup(name) {
+            Some(value) => out.push_str(&value),
+            None => anyhow::bail!("Template error: unknown variable `{{{{{}}}}}`", name),
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(content: &str, fence: &str) -> TemplateContext {
+        let file = TemplateFile { path: "README.md".to_string(), language: "markdown".to_string(), tokens: 7, fence: fence.to_string(), content: content.to_string() };
+        TemplateContext { root: ".".to_string(), tree: String::new(), project_context: String::new(), files: vec![file], total_tokens: 7 }
+    }
+
+    #[test]
+    fn default_files_fence_outlasts_the_content() {
+        let ctx = context("```rust\nfn main() {}\n```", "````");
+        assert_eq!(render("{{files}}", &ctx).unwrap(), "--- File: README.md (7 tokens) ---\n````markdown\n```rust\nfn main() {}\n```\n````\n\n");
+    }
+
+    #[test]
+    fn fence_is_a_per_file_variable() {
+        let ctx = context("x", "```");
+        assert_eq!(render("{{#each files}}{{fence}}{{language}}\n{{content}}\n{{fence}}{{/each}}", &ctx).unwrap(), "```markdown\nx\n```");
+        assert!(render("{{fence}}", &ctx).is_err());
+    }
+}
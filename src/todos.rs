@@ -0,0 +1,48 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+// DEBT MARKERS
+// `gimtex todos`: TODO/FIXME/HACK/XXX markers with a few lines around each, grouped per
+// file. Markers are matched in upper case only, so `todo_list` variables and prose don't
+// count; an optional `(owner)` and `:` after the tag are dropped from the message.
+
+// Lines shown on each side of a marker
+pub const CONTEXT_LINES: usize = 3;
+
+pub const KINDS: [&str; 4] = ["TODO", "FIXME", "HACK", "XXX"];
+
+pub struct Marker {
+    // 1-based
+    pub line: usize,
+    pub kind: &'static str,
+    pub message: String,
+}
+
+pub fn markers(content: &str) -> Vec<Marker> {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker_re = MARKER.get_or_init(|| Regex::new(r"\b(TODO|FIXME|HACK|XXX)\b(?:\([^)]*\))?:?(.*)").expect("static marker pattern"));
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let caps = marker_re.captures(line)?;
+            let kind = KINDS.into_iter().find(|k| *k == &caps[1])?;
+            // Block comment closers trail the message on single-line `/* TODO ... */`
+            let message = caps[2].trim().trim_end_matches("*/").trim_end_matches("-->").trim().to_string();
+            Some(Marker { line: index + 1, kind, message })
+        })
+        .collect()
+}
+
+// The marker line and its neighbours, numbered, with the marker line flagged by `>`
+pub fn excerpt(lines: &[&str], line: usize) -> String {
+    let first = line.saturating_sub(CONTEXT_LINES + 1);
+    let last = (line + CONTEXT_LINES).min(lines.len());
+    let width = last.to_string().len();
+    let mut out = String::new();
+    for (index, text) in lines.iter().enumerate().take(last).skip(first) {
+        let flag = if index + 1 == line { '>' } else { ' ' };
+        out.push_str(&format!("  {} {:>width$} | {}\n", flag, index + 1, text, width = width));
+    }
+    out
+}
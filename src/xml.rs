@@ -0,0 +1,99 @@
+// XML Output: every document is wrapped in <repository> and file bodies go
+// into CDATA, so the payload parses with any conforming XML library.
+
+// XML 1.0 forbids most C0 control characters even when escaped; swap them for U+FFFD.
+fn sanitize_char(c: char) -> Option<char> {
+    match c {
+        '\t' | '\n' | '\r' => Some(c),
+        c if (c as u32) < 0x20 => Some('\u{FFFD}'),
+        '\u{FFFE}' | '\u{FFFF}' => Some('\u{FFFD}'),
+        _ => None,
+    }
+}
+
+pub fn escape_attr(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push_str("&#10;"),
+            '\t' => out.push_str("&#9;"),
+            _ => out.push(sanitize_char(c).unwrap_or(c)),
+        }
+    }
+    out
+}
+
+// A literal `]]>` would end the section early, so it is split across two CDATA blocks.
+pub fn cdata(text: &str) -> String {
+    let clean: String = text.chars().map(|c| sanitize_char(c).unwrap_or(c)).collect();
+    format!("<![CDATA[{}]]>", clean.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+pub fn document_start(root: &str) -> String {
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<repository root=\"{}\">\n", escape_attr(root))
+}
+
+pub fn element(name: &str, text: &str) -> String {
+    format!("<{}>{}</{}>\n", name, cdata(text), name)
+}
+
+// `tokens` is None in --no-tokens mode, where byte/line counts stand in.
+pub fn file_element(path: &str, tokens: Option<usize>, extra: &[(&str, &str)], text: &str) -> String {
+    let mut attrs = match tokens {
+        Some(t) => format!("tokens=\"{}\"", t),
+        None => format!("bytes=\"{}\" lines=\"{}\"", text.len(), text.lines().count()),
+    };
+    for (name, value) in extra {
+        attrs.push_str(&format!(" {}=\"{}\"", name, escape_attr(value)));
+    }
+    format!("<file path=\"{}\" {}>{}</file>\n", escape_attr(path), attrs, cdata(text))
+}
+
+pub fn document_end() -> &'static str {
+    "</repository>\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_escape_markup_quotes_and_whitespace() {
+        assert_eq!(escape_attr(r#"a&b<c>d"e'f"#), "a&amp;b&lt;c&gt;d&quot;e&apos;f");
+        assert_eq!(escape_attr("one\ntwo\tthree"), "one&#10;two&#9;three");
+    }
+
+    #[test]
+    fn forbidden_control_characters_become_replacement_chars() {
+        assert_eq!(escape_attr("bell\u{7}"), "bell\u{FFFD}");
+        assert_eq!(cdata("nul\0 esc\u{1B} \u{FFFE}\u{FFFF}"), "<![CDATA[nul\u{FFFD} esc\u{FFFD} \u{FFFD}\u{FFFD}]]>");
+        assert_eq!(cdata("keep\t\r\n"), "<![CDATA[keep\t\r\n]]>");
+    }
+
+    #[test]
+    fn cdata_terminators_are_split() {
+        assert_eq!(cdata("a]]>b"), "<![CDATA[a]]]]><![CDATA[>b]]>");
+        assert_eq!(cdata("]]>]]>"), "<![CDATA[]]]]><![CDATA[>]]]]><![CDATA[>]]>");
+        // Reassembling the sections gives the original text back
+        let joined = cdata("x]]>y").replace("]]><![CDATA[", "");
+        assert_eq!(joined, "<![CDATA[x]]>y]]>");
+    }
+
+    #[test]
+    fn file_elements_escape_path_and_extra_attributes() {
+        let out = file_element("a&b/\"q\".rs", Some(3), &[("note", "<x>")], "fn f() {}");
+        assert_eq!(out, "<file path=\"a&amp;b/&quot;q&quot;.rs\" tokens=\"3\" note=\"&lt;x&gt;\"><![CDATA[fn f() {}]]></file>\n");
+        let out = file_element("a.rs", None, &[], "one\ntwo\n");
+        assert!(out.starts_with("<file path=\"a.rs\" bytes=\"8\" lines=\"2\">"), "{}", out);
+    }
+
+    #[test]
+    fn document_root_attribute_is_escaped() {
+        assert_eq!(document_start("<tmp>&co"), "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<repository root=\"&lt;tmp&gt;&amp;co\">\n");
+    }
+}
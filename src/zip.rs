@@ -0,0 +1,85 @@
+use anyhow::{bail, Context, Result};
+use std::io::Read;
+
+// ZIP READER
+// Just enough of the format for DOCX bodies and zip targets: the central directory,
+// stored and deflated entries. No ZIP64, no encryption.
+
+pub struct Entry {
+    pub name: String,
+    method: u16,
+    compressed_size: usize,
+    pub size: u64,
+    // Unix symlink (made by a Unix zip with mode S_IFLNK in the external attributes)
+    pub symlink: bool,
+    local_offset: usize,
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Result<u16> {
+    let b = bytes.get(at..at + 2).context("Truncated zip")?;
+    Ok(u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Result<u32> {
+    let b = bytes.get(at..at + 4).context("Truncated zip")?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+pub fn entries(bytes: &[u8]) -> Result<Vec<Entry>> {
+    // End of central directory: last 22 bytes plus an optional comment of up to 64 KB
+    let search_from = bytes.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (search_from..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&at| bytes[at..].starts_with(b"PK\x05\x06"))
+        .context("Not a zip file (no central directory)")?;
+    let count = u16_at(bytes, end + 10)? as usize;
+    let mut at = u32_at(bytes, end + 16)? as usize;
+    // The ZIP64 locator sits right before the classic record when either one is in use
+    let locator = end >= 20 && bytes[end - 20..].starts_with(b"PK\x06\x07");
+    if locator || count == u16::MAX as usize || at == u32::MAX as usize {
+        bail!("ZIP64 archives are not supported");
+    }
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        if !bytes.get(at..).is_some_and(|b| b.starts_with(b"PK\x01\x02")) {
+            bail!("Corrupt zip central directory");
+        }
+        let name_len = u16_at(bytes, at + 28)? as usize;
+        let extra_len = u16_at(bytes, at + 30)? as usize;
+        let comment_len = u16_at(bytes, at + 32)? as usize;
+        let name = bytes.get(at + 46..at + 46 + name_len).context("Truncated zip")?;
+        let (compressed_size, size, local_offset) = (u32_at(bytes, at + 20)?, u32_at(bytes, at + 24)?, u32_at(bytes, at + 42)?);
+        // Saturated fields defer to a ZIP64 extra field: refuse up front, before any entry is read
+        if [compressed_size, size, local_offset].contains(&u32::MAX) {
+            bail!("ZIP64 archives are not supported");
+        }
+        entries.push(Entry {
+            name: String::from_utf8_lossy(name).to_string(),
+            method: u16_at(bytes, at + 10)?,
+            compressed_size: compressed_size as usize,
+            size: size as u64,
+            symlink: bytes[at + 5] == 3 && (u32_at(bytes, at + 38)? >> 16) & 0o170000 == 0o120000,
+            local_offset: local_offset as usize,
+        });
+        at += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+// Entry contents, reading at most `limit` bytes of output
+pub fn read(bytes: &[u8], entry: &Entry, limit: u64) -> Result<Vec<u8>> {
+    let at = entry.local_offset;
+    if !bytes.get(at..).is_some_and(|b| b.starts_with(b"PK\x03\x04")) {
+        bail!("Corrupt zip entry: {}", entry.name);
+    }
+    let start = at + 30 + u16_at(bytes, at + 26)? as usize + u16_at(bytes, at + 28)? as usize;
+    let data = bytes.get(start..start + entry.compressed_size).context("Truncated zip")?;
+    let mut out = Vec::new();
+    match entry.method {
+        0 => data.take(limit).read_to_end(&mut out)?,
+        8 => flate2::read::DeflateDecoder::new(data).take(limit).read_to_end(&mut out)?,
+        method => bail!("Unsupported zip compression method {} for {}", method, entry.name),
+    };
+    Ok(out)
+}